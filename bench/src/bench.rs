@@ -239,4 +239,6 @@ macro_rules! bench_find {
 mod ffi;
 mod misc;
 mod regexdna;
+#[cfg(feature = "re-rust")]
+mod rust_captures;
 mod sherlock;
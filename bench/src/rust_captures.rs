@@ -0,0 +1,59 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Compares iterating over captures with a fresh allocation per match against
+// reusing a single slot buffer.
+
+use std::iter::repeat;
+
+use test::Bencher;
+
+use regex::internal::{Exec, ExecBuilder, RegularExpression};
+
+fn exec() -> Exec {
+    ExecBuilder::new(r"([a-z]+)([0-9]+)").build().unwrap()
+}
+
+fn text() -> String {
+    repeat("foo123 bar45 ").take(1000).collect()
+}
+
+#[bench]
+fn captures_iter_alloc(b: &mut Bencher) {
+    let exec = exec();
+    let text = text();
+    b.bytes = text.len() as u64;
+    b.iter(|| {
+        let mut count = 0;
+        for locs in exec.searcher_str().captures_iter(&text) {
+            if locs.pos(2).is_some() {
+                count += 1;
+            }
+        }
+        assert_eq!(2000, count);
+    });
+}
+
+#[bench]
+fn captures_iter_reuse(b: &mut Bencher) {
+    let exec = exec();
+    let text = text();
+    b.bytes = text.len() as u64;
+    b.iter(|| {
+        let mut count = 0;
+        let mut it = exec.searcher_str().captures_iter_reuse(&text);
+        while let Some(slots) = it.next_slots() {
+            if slots[4].is_some() {
+                count += 1;
+            }
+        }
+        assert_eq!(2000, count);
+    });
+}
@@ -18,7 +18,15 @@ fi
 ci/run-shootout-test
 
 # Run tests on regex-syntax crate.
-cargo test --verbose --manifest-path regex-syntax/Cargo.toml
+if [ "$TRAVIS_RUST_VERSION" = "1.12.0" ]; then
+  # The tests depend on serde_json, which needs a newer Rust than the oldest
+  # one we support, so only check that the crate builds.
+  cargo build --verbose --manifest-path regex-syntax/Cargo.toml
+else
+  cargo test --verbose --manifest-path regex-syntax/Cargo.toml
+  cargo test --verbose --manifest-path regex-syntax/Cargo.toml \
+    --features serde
+fi
 cargo doc --verbose --manifest-path regex-syntax/Cargo.toml
 
 # Run tests on regex-capi crate.
@@ -0,0 +1,175 @@
+// Copyright 2014-2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::{self, Write};
+
+use Expr;
+
+/// Writes `expr` as a JSON object to `out`.
+///
+/// Every object has a `"type"` key naming the kind of expression in snake
+/// case. The remaining keys depend on the type.
+pub fn write_expr<W: Write>(out: &mut W, expr: &Expr) -> fmt::Result {
+    match *expr {
+        Expr::Empty => write_type(out, "empty"),
+        Expr::Literal { ref chars, casei } => {
+            let lit: String = chars.iter().cloned().collect();
+            try!(write!(out, r#"{{"type":"literal","value":"#));
+            try!(write_str(out, &lit));
+            write!(out, r#","casei":{}}}"#, casei)
+        }
+        Expr::LiteralBytes { ref bytes, casei } => {
+            try!(write!(out, r#"{{"type":"literal_bytes","value":["#));
+            for (i, b) in bytes.iter().enumerate() {
+                if i > 0 { try!(write!(out, ",")); }
+                try!(write!(out, "{}", b));
+            }
+            write!(out, r#"],"casei":{}}}"#, casei)
+        }
+        Expr::AnyChar => write_type(out, "any_char"),
+        Expr::AnyCharNoNL => write_type(out, "any_char_no_nl"),
+        Expr::AnyByte => write_type(out, "any_byte"),
+        Expr::AnyByteNoNL => write_type(out, "any_byte_no_nl"),
+        Expr::Class(ref cls) => {
+            try!(write!(out, r#"{{"type":"class","ranges":["#));
+            for (i, r) in cls.iter().enumerate() {
+                if i > 0 { try!(write!(out, ",")); }
+                try!(write!(out, "["));
+                try!(write_str(out, &r.start.to_string()));
+                try!(write!(out, ","));
+                try!(write_str(out, &r.end.to_string()));
+                try!(write!(out, "]"));
+            }
+            write!(out, "]}}")
+        }
+        Expr::ClassBytes(ref cls) => {
+            try!(write!(out, r#"{{"type":"class_bytes","ranges":["#));
+            for (i, r) in cls.iter().enumerate() {
+                if i > 0 { try!(write!(out, ",")); }
+                try!(write!(out, "[{},{}]", r.start, r.end));
+            }
+            write!(out, "]}}")
+        }
+        Expr::StartLine => write_type(out, "start_line"),
+        Expr::EndLine => write_type(out, "end_line"),
+        Expr::StartText => write_type(out, "start_text"),
+        Expr::EndText => write_type(out, "end_text"),
+        Expr::WordBoundary => write_type(out, "word_boundary"),
+        Expr::NotWordBoundary => write_type(out, "not_word_boundary"),
+        Expr::WordBoundaryAscii => write_type(out, "word_boundary_ascii"),
+        Expr::NotWordBoundaryAscii => {
+            write_type(out, "not_word_boundary_ascii")
+        }
+        Expr::Group { ref e, i, ref name } => {
+            try!(write!(out, r#"{{"type":"group","index":"#));
+            match i {
+                None => try!(write!(out, "null")),
+                Some(i) => try!(write!(out, "{}", i)),
+            }
+            try!(write!(out, r#","name":"#));
+            match *name {
+                None => try!(write!(out, "null")),
+                Some(ref name) => try!(write_str(out, name)),
+            }
+            try!(write!(out, r#","expr":"#));
+            try!(write_expr(out, e));
+            write!(out, "}}")
+        }
+        Expr::Repeat { ref e, r, greedy } => {
+            let (min, max) = r.bounds();
+            try!(write!(out, r#"{{"type":"repeat","min":{},"max":"#, min));
+            match max {
+                None => try!(write!(out, "null")),
+                Some(max) => try!(write!(out, "{}", max)),
+            }
+            try!(write!(out, r#","greedy":{},"expr":"#, greedy));
+            try!(write_expr(out, e));
+            write!(out, "}}")
+        }
+        Expr::Concat(ref es) => write_list(out, "concat", es),
+        Expr::Alternate(ref es) => write_list(out, "alternate", es),
+    }
+}
+
+fn write_type<W: Write>(out: &mut W, name: &str) -> fmt::Result {
+    write!(out, r#"{{"type":"{}"}}"#, name)
+}
+
+fn write_list<W: Write>(
+    out: &mut W,
+    name: &str,
+    es: &[Expr],
+) -> fmt::Result {
+    try!(write!(out, r#"{{"type":"{}","exprs":["#, name));
+    for (i, e) in es.iter().enumerate() {
+        if i > 0 { try!(write!(out, ",")); }
+        try!(write_expr(out, e));
+    }
+    write!(out, "]}}")
+}
+
+/// Writes `s` as a JSON string literal.
+fn write_str<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    try!(out.write_char('"'));
+    for c in s.chars() {
+        match c {
+            '"' => try!(out.write_str(r#"\""#)),
+            '\\' => try!(out.write_str(r"\\")),
+            '\n' => try!(out.write_str(r"\n")),
+            '\r' => try!(out.write_str(r"\r")),
+            '\t' => try!(out.write_str(r"\t")),
+            c if (c as u32) < 0x20 => {
+                try!(write!(out, r"\u{:04x}", c as u32))
+            }
+            c => try!(out.write_char(c)),
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use {Expr, ExprBuilder};
+
+    fn json(re: &str) -> String {
+        Expr::parse(re).unwrap().to_json()
+    }
+
+    #[test]
+    fn small_pattern() {
+        assert_eq!(json(r"^(?P<x>a|b[0-9])*?$"), concat!(
+            r#"{"type":"concat","exprs":["#,
+            r#"{"type":"start_text"},"#,
+            r#"{"type":"repeat","min":0,"max":null,"greedy":false,"expr":"#,
+            r#"{"type":"group","index":1,"name":"x","expr":"#,
+            r#"{"type":"alternate","exprs":["#,
+            r#"{"type":"literal","value":"a","casei":false},"#,
+            r#"{"type":"concat","exprs":["#,
+            r#"{"type":"literal","value":"b","casei":false},"#,
+            r#"{"type":"class","ranges":[["0","9"]]}"#,
+            r#"]}]}}},"#,
+            r#"{"type":"end_text"}"#,
+            r#"]}"#,
+        ));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(json(r#"(?i)"\\\n\x01"#), concat!(
+            r#"{"type":"literal","value":"\"\\\n\u0001","casei":true}"#,
+        ));
+        let expr = ExprBuilder::new().allow_bytes(true)
+            .parse(r"(?-u)[\x00-\xFF]{2}").unwrap();
+        assert_eq!(expr.to_json(), concat!(
+            r#"{"type":"repeat","min":2,"max":2,"greedy":true,"expr":"#,
+            r#"{"type":"class_bytes","ranges":[[0,255]]}}"#,
+        ));
+    }
+}
@@ -58,27 +58,40 @@ let err = Expr::parse(r"ab(cd").unwrap_err();
 assert_eq!(err.position(), 2);
 assert_eq!(err.kind(), &ErrorKind::UnclosedParen);
 ```
+
+# Crate features
+
+The `serde` feature implements `Serialize` and `Deserialize` for `Expr` and
+the types it is built from. It is disabled by default.
 */
 
 #![deny(missing_docs)]
 #![cfg_attr(test, deny(warnings))]
 
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
 #[cfg(test)] extern crate quickcheck;
 #[cfg(test)] extern crate rand;
+#[cfg(all(test, feature = "serde"))] extern crate serde_json;
 
+mod json;
 mod literals;
 mod parser;
+mod railroad;
 mod unicode;
+mod visitor;
 
 use std::ascii;
 use std::char;
+use std::collections::HashMap;
 use std::cmp::{Ordering, max, min};
 use std::fmt;
-use std::iter::IntoIterator;
+use std::iter::{IntoIterator, repeat};
+use std::mem;
 use std::ops::Deref;
 use std::result;
 use std::slice;
 use std::u8;
+use std::usize;
 use std::vec;
 
 use unicode::case_folding;
@@ -86,14 +99,19 @@ use unicode::case_folding;
 use self::Expr::*;
 use self::Repeater::*;
 
-use parser::{Flags, Parser};
+use parser::{
+    Flags, Parser, enclosing_group, split_alternates, suggest_unicode_class,
+};
 
 pub use literals::{Literals, Lit};
+pub use railroad::RailroadNode;
+pub use visitor::{Folder, PreorderIter, Visitor, fold, visit};
 
 /// A regular expression abstract syntax tree.
 ///
 /// An `Expr` represents the abstract syntax of a regular expression.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Expr {
     /// An empty regex (which never matches any text).
     Empty,
@@ -183,7 +201,8 @@ type CaptureIndex = Option<usize>;
 type CaptureName = Option<String>;
 
 /// The type of a repeat operator expression.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Repeater {
     /// Match zero or one (`?`).
     ZeroOrOne,
@@ -203,6 +222,21 @@ pub enum Repeater {
 }
 
 impl Repeater {
+    /// Returns the minimum and maximum number of times this repetition
+    /// matches. A maximum of `None` means that there is no upper bound.
+    ///
+    /// e.g., `?` is `(0, Some(1))`, `+` is `(1, None)` and `{2}` is
+    /// `(2, Some(2))`.
+    pub fn bounds(&self) -> (u32, Option<u32>) {
+        use self::Repeater::*;
+        match *self {
+            ZeroOrOne => (0, Some(1)),
+            ZeroOrMore => (0, None),
+            OneOrMore => (1, None),
+            Range { min, max } => (min, max),
+        }
+    }
+
     /// Returns true if and only if this repetition can match the empty string.
     fn matches_empty(&self) -> bool {
         use self::Repeater::*;
@@ -235,7 +269,8 @@ impl Repeater {
 /// If the case insensitive flag was set when parsing a character class, then
 /// simple case folding is done automatically. For example, `(?i)[a-c]` is
 /// automatically translated to `[a-cA-C]`.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CharClass {
     ranges: Vec<ClassRange>,
 }
@@ -248,7 +283,8 @@ pub struct CharClass {
 ///
 /// Note that this has a few convenient impls on `PartialEq` and `PartialOrd`
 /// for testing whether a character is contained inside a given range.
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ClassRange {
     /// The start character of the range.
     ///
@@ -280,7 +316,8 @@ pub struct ClassRange {
 /// If the case insensitive flag was set when parsing a character class,
 /// then simple ASCII-only case folding is done automatically. For example,
 /// `(?i)[a-c]` is automatically translated to `[a-cA-C]`.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ByteClass {
     ranges: Vec<ByteRange>,
 }
@@ -289,7 +326,8 @@ pub struct ByteClass {
 ///
 /// Note that this has a few convenient impls on `PartialEq` and `PartialOrd`
 /// for testing whether a byte is contained inside a given range.
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ByteRange {
     /// The start byte of the range.
     ///
@@ -310,16 +348,20 @@ pub struct ByteRange {
 pub struct ExprBuilder {
     flags: Flags,
     nest_limit: usize,
+    stop_chars: Vec<char>,
 }
 
 impl ExprBuilder {
     /// Create a new builder for configuring expression parsing.
     ///
-    /// Note that all flags are disabled by default.
+    /// Note that all flags are disabled by default. Groups and character
+    /// classes may each nest at most 200 deep, which can be changed with
+    /// `nest_limit`.
     pub fn new() -> ExprBuilder {
         ExprBuilder {
-            flags: Flags::default(),
+            flags: Flags { class_nest_limit: 200, ..Flags::default() },
             nest_limit: 200,
+            stop_chars: vec![],
         }
     }
 
@@ -365,6 +407,35 @@ impl ExprBuilder {
         }
     }
 
+    /// Enable the default value of every flag named in `flags`.
+    ///
+    /// Each character must be one of the flags that may appear in a group,
+    /// i.e., `i`, `m`, `s`, `U`, `u` or `x`. For example, `"imx"` is
+    /// equivalent to calling `case_insensitive(true)`, `multi_line(true)`
+    /// and `ignore_whitespace(true)`.
+    ///
+    /// If an unrecognized flag is found, then an `UnrecognizedFlag` error is
+    /// returned whose position is the offset of that flag in `flags`.
+    pub fn with_default_flags(self, flags: &str) -> Result<ExprBuilder> {
+        let mut builder = self;
+        for (i, c) in flags.chars().enumerate() {
+            builder = match c {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'U' => builder.swap_greed(true),
+                'u' => builder.unicode(true),
+                'x' => builder.ignore_whitespace(true),
+                c => return Err(Error {
+                    pos: i,
+                    surround: flags.to_owned(),
+                    kind: ErrorKind::UnrecognizedFlag(c),
+                }),
+            };
+        }
+        Ok(builder)
+    }
+
     /// Whether the parser allows matching arbitrary bytes or not.
     ///
     /// When the `u` flag is disabled (either with this builder or in the
@@ -382,19 +453,180 @@ impl ExprBuilder {
         self
     }
 
+    /// Whether whitespace is permitted around Unicode class names.
+    ///
+    /// When enabled, an expression like `\p{ Greek }` is accepted and is
+    /// equivalent to `\p{Greek}`, even when the ignore whitespace (`x`) flag
+    /// is disabled. Whitespace is only ignored around the name, so
+    /// `\p{Old Italic}` is still rejected.
+    ///
+    /// This is disabled by default.
+    pub fn lenient_property_names(mut self, yes: bool) -> ExprBuilder {
+        self.flags.lenient_property_names = yes;
+        self
+    }
+
     /// Set the nesting limit for regular expression parsing.
     ///
     /// Regular expressions that nest more than this limit will result in a
-    /// `StackExhausted` error.
+    /// `StackExhausted` error. Character classes that nest more than this
+    /// limit will result in a `ClassNestLimitExceeded` error.
+    ///
+    /// The default limit is 200.
     pub fn nest_limit(mut self, limit: usize) -> ExprBuilder {
+        self.flags.class_nest_limit = limit;
         self.nest_limit = limit;
         self
     }
 
+    /// Set the characters that end a regular expression parsed with
+    /// `parse_partial`.
+    ///
+    /// This is useful when a regular expression is embedded in some other
+    /// syntax and is followed by a delimiter, e.g., `/` in `/abc/`. A stop
+    /// character only ends the expression outside of character classes and
+    /// escapes. Inside a class it is a literal, so `[/]` matches `/`. It may
+    /// also be escaped to match it literally, e.g., `\/`, unless the escape
+    /// already means something else (such as `\n`). No stop characters are
+    /// set by default.
+    pub fn stop_chars(mut self, chars: &[char]) -> ExprBuilder {
+        self.stop_chars = chars.to_vec();
+        self
+    }
+
+    /// Parse the longest prefix of a string that forms a regular expression
+    /// using the current configuration.
+    ///
+    /// Parsing stops cleanly at the end of the string, at a stop character
+    /// (see `stop_chars`) or at a `)` that doesn't close a group. The
+    /// expression is returned along with the byte offset in `s` of the
+    /// first character that wasn't parsed, so `&s[offset..]` is the
+    /// remaining input.
+    ///
+    /// Stopping inside a group is an error, e.g., `(a/b)` with a stop
+    /// character of `/` results in an `UnclosedParen` error.
+    pub fn parse_partial(self, s: &str) -> Result<(Expr, usize)> {
+        let (expr, end) =
+            try!(Parser::parse_partial(s, self.flags, &self.stop_chars));
+        Ok((try!(expr.simplify(self.nest_limit)), end))
+    }
+
     /// Parse a string as a regular expression using the current configuraiton.
     pub fn parse(self, s: &str) -> Result<Expr> {
         Parser::parse(s, self.flags).and_then(|e| e.simplify(self.nest_limit))
     }
+
+    /// Parse a string as a regular expression, reporting as many errors as
+    /// possible instead of only the first.
+    ///
+    /// When `s` fails to parse, each top level alternative (e.g., `a` and
+    /// `b` in `a|b`) is checked on its own, so that errors in different
+    /// alternatives are all reported. Within an alternative, checking resumes
+    /// after the `)` or `]` that closes the innermost group or class around
+    /// each error, so `(a**)(b**)` reports two errors. An error outside of
+    /// any group or class ends the checking of its alternative. Error
+    /// positions are relative to `s`.
+    ///
+    /// Since alternatives are checked separately, flags set in one
+    /// alternative don't carry over into the ones after it.
+    ///
+    /// The expression is returned only if `s` parses without errors.
+    pub fn parse_collect_errors(self, s: &str) -> (Option<Expr>, Vec<Error>) {
+        let err = match self.clone().parse(s) {
+            Ok(expr) => return (Some(expr), vec![]),
+            Err(err) => err,
+        };
+        let chars: Vec<char> = s.chars().collect();
+        let mut errs = vec![];
+        for (start, end) in split_alternates(&chars) {
+            let mut alt = chars[start..end].to_vec();
+            loop {
+                let pat: String = alt.iter().cloned().collect();
+                let e = match self.clone().parse(&pat) {
+                    Ok(_) => break,
+                    Err(e) => e,
+                };
+                let span = enclosing_group(&alt, e.pos);
+                let pos = start + e.pos;
+                let around = max(5, pos) - 5..min(chars.len(), pos + 5);
+                errs.push(Error {
+                    pos: pos,
+                    surround: chars[around].iter().cloned().collect(),
+                    kind: e.kind,
+                });
+                // Blank out the group or class with an equally long group of
+                // literals, which keeps the positions of later errors.
+                let (open, close) = match span {
+                    None => break,
+                    Some(span) => span,
+                };
+                let mut changed = false;
+                for i in open + 1..close - 1 {
+                    changed = changed || alt[i] != 'x';
+                    alt[i] = 'x';
+                }
+                changed = changed || alt[open] != '(' || alt[close - 1] != ')';
+                alt[open] = '(';
+                alt[close - 1] = ')';
+                if !changed {
+                    break;
+                }
+            }
+        }
+        if errs.is_empty() {
+            // The error involves more than one alternative, e.g., a
+            // duplicate capture name or an empty alternate.
+            errs.push(err);
+        }
+        (None, errs)
+    }
+
+    /// Returns a parser that can parse many strings with the current
+    /// configuration.
+    ///
+    /// This is useful when parsing a large number of regular expressions
+    /// with identical settings, since the parser reuses its internal buffers
+    /// across calls.
+    pub fn build_many(&self) -> ExprParser {
+        ExprParser {
+            parser: Parser::new(self.flags),
+            flags: self.flags,
+            nest_limit: self.nest_limit,
+        }
+    }
+}
+
+/// A reusable parser for regular expressions.
+///
+/// An `ExprParser` is created by `ExprBuilder::build_many`. Every call to
+/// `parse` starts from a clean state, so the result is the same as calling
+/// `ExprBuilder::parse` with the same configuration. Parsing requires
+/// mutable access to the parser's buffers, so a single `ExprParser` can't
+/// be shared between threads. Use one per thread instead.
+#[derive(Debug)]
+pub struct ExprParser {
+    parser: Parser,
+    flags: Flags,
+    nest_limit: usize,
+}
+
+impl ExprParser {
+    /// Parse a string as a regular expression.
+    pub fn parse(&mut self, s: &str) -> Result<Expr> {
+        self.parser.parse_reusing(s, self.flags)
+            .and_then(|e| e.simplify(self.nest_limit))
+    }
+
+    /// Release the memory held by the parser's buffers.
+    ///
+    /// The buffers grow to fit the largest expression parsed so far and are
+    /// otherwise kept between calls to `parse`. After parsing an unusually
+    /// large expression, this can be used to give that memory back. Only
+    /// memory is released: no parse state is ever carried from one call to
+    /// `parse` to the next, and the configuration is kept.
+    pub fn reset(&mut self) {
+        self.parser = Parser::new(self.flags);
+    }
 }
 
 impl Expr {
@@ -499,6 +731,236 @@ impl Expr {
         simp(self, 0, nest_limit)
     }
 
+    /// Removes `Empty` sub-expressions that have no effect on matching.
+    ///
+    /// An `Empty` inside a concatenation matches nothing and is dropped. A
+    /// concatenation left with no elements becomes `Empty` and one left with
+    /// a single element is replaced by that element. An `Empty` branch of an
+    /// alternation is kept, since it is the alternative that matches the
+    /// empty string and removing it would change what the alternation
+    /// matches.
+    pub fn simplify_empty(self) -> Expr {
+        match self {
+            Repeat { e, r, greedy } => Repeat {
+                e: Box::new(e.simplify_empty()),
+                r: r,
+                greedy: greedy,
+            },
+            Group { e, i, name } => Group {
+                e: Box::new(e.simplify_empty()),
+                i: i,
+                name: name,
+            },
+            Concat(es) => {
+                let mut new_es: Vec<Expr> = es.into_iter()
+                    .map(Expr::simplify_empty)
+                    .filter(|e| *e != Empty)
+                    .collect();
+                match new_es.len() {
+                    0 => Empty,
+                    1 => new_es.pop().unwrap(),
+                    _ => Concat(new_es),
+                }
+            }
+            Alternate(es) => {
+                Alternate(es.into_iter().map(Expr::simplify_empty).collect())
+            }
+            e => e,
+        }
+    }
+
+    /// Merges concatenations directly inside concatenations, and
+    /// alternations directly inside alternations, into their parent.
+    ///
+    /// The parser never produces such nesting, but expressions built or
+    /// rewritten by hand may contain it, e.g., `Concat[Concat[a, b], c]`
+    /// becomes `Concat[a, b, c]`. What the expression matches is unchanged.
+    pub fn flatten(self) -> Expr {
+        match self {
+            Repeat { e, r, greedy } => Repeat {
+                e: Box::new(e.flatten()),
+                r: r,
+                greedy: greedy,
+            },
+            Group { e, i, name } => Group {
+                e: Box::new(e.flatten()),
+                i: i,
+                name: name,
+            },
+            Concat(es) => {
+                let mut new_es = Vec::with_capacity(es.len());
+                for e in es {
+                    match e.flatten() {
+                        Concat(es) => new_es.extend(es),
+                        e => new_es.push(e),
+                    }
+                }
+                Concat(new_es)
+            }
+            Alternate(es) => {
+                let mut new_es = Vec::with_capacity(es.len());
+                for e in es {
+                    match e.flatten() {
+                        Alternate(es) => new_es.extend(es),
+                        e => new_es.push(e),
+                    }
+                }
+                Alternate(new_es)
+            }
+            e => e,
+        }
+    }
+
+    /// Collapses directly nested `*` and `+` repetitions into one.
+    ///
+    /// `(?:x*)*`, `(?:x*)+` and `(?:x+)*` become `x*`, and `(?:x+)+` becomes
+    /// `x+`. These match the same text, but the nested forms can be very
+    /// slow for backtracking engines. Repetitions are only collapsed when
+    /// both are greedy or both are lazy, and only through non-capturing
+    /// groups, since removing a capturing group would change which
+    /// submatches are reported.
+    pub fn simplify_repetitions(self) -> Expr {
+        match self {
+            Repeat { e, r, greedy } => {
+                let e = e.simplify_repetitions();
+                let collapsed = {
+                    let inner = match e {
+                        Group { ref e, i: None, name: None } => &**e,
+                        ref e => e,
+                    };
+                    match *inner {
+                        Repeat { r: inner_r, greedy: inner_greedy, .. }
+                            if inner_greedy == greedy => {
+                            match (inner_r, r) {
+                                (OneOrMore, OneOrMore) => Some(OneOrMore),
+                                (ZeroOrMore, ZeroOrMore)
+                                | (ZeroOrMore, OneOrMore)
+                                | (OneOrMore, ZeroOrMore) => Some(ZeroOrMore),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    }
+                };
+                match (collapsed, e.into_bare()) {
+                    (Some(r), Repeat { e, .. }) => {
+                        Repeat { e: e, r: r, greedy: greedy }
+                    }
+                    (_, e) => Repeat {
+                        e: Box::new(e.into_repeat_item()),
+                        r: r,
+                        greedy: greedy,
+                    },
+                }
+            }
+            Group { e, i, name } => Group {
+                e: Box::new(e.simplify_repetitions()),
+                i: i,
+                name: name,
+            },
+            Concat(es) => {
+                Concat(
+                    es.into_iter().map(Expr::simplify_repetitions).collect())
+            }
+            Alternate(es) => {
+                Alternate(
+                    es.into_iter().map(Expr::simplify_repetitions).collect())
+            }
+            e => e,
+        }
+    }
+
+    /// Returns a canonical string representation of this expression.
+    ///
+    /// Syntax that has no effect on matching is removed before printing:
+    /// repetitions of exactly one (`a{1}`) are unwrapped, alternations with a
+    /// single branch are replaced by that branch, non-capturing groups that
+    /// aren't needed for precedence are dropped and adjacent literals are
+    /// merged. Expressions with equal canonical strings match the same text,
+    /// but the converse doesn't hold.
+    pub fn to_canonical_string(&self) -> String {
+        let e = self.clone().unwrap_redundant().into_bare().simplify_empty();
+        // Simplification only fails when the nest limit is exceeded.
+        e.simplify(usize::MAX).unwrap().to_string()
+    }
+
+    /// Removes repetitions of exactly one, single branch alternations and
+    /// non-capturing groups that directly contain a concatenation item or an
+    /// alternation branch.
+    fn unwrap_redundant(self) -> Expr {
+        match self {
+            Repeat { e, r: Range { min: 1, max: Some(1) }, .. } => {
+                e.unwrap_redundant()
+            }
+            Repeat { e, r, greedy } => Repeat {
+                e: Box::new(e.unwrap_redundant()),
+                r: r,
+                greedy: greedy,
+            },
+            Group { e, i, name } => Group {
+                e: Box::new(e.unwrap_redundant()),
+                i: i,
+                name: name,
+            },
+            Concat(es) => {
+                let mut new_es = Vec::with_capacity(es.len());
+                for e in es {
+                    let e = match e.unwrap_redundant() {
+                        Group { e, i: None, name: None } => match *e {
+                            // e.g., `(?:a|b)c`
+                            Alternate(es) => Group {
+                                e: Box::new(Alternate(es)),
+                                i: None,
+                                name: None,
+                            },
+                            e => e,
+                        },
+                        e => e,
+                    };
+                    match e {
+                        Concat(es) => new_es.extend(es),
+                        e => new_es.push(e),
+                    }
+                }
+                Concat(new_es)
+            }
+            Alternate(es) => {
+                let mut new_es = Vec::with_capacity(es.len());
+                for e in es {
+                    match e.unwrap_redundant().into_bare() {
+                        Alternate(es) => new_es.extend(es),
+                        e => new_es.push(e),
+                    }
+                }
+                if new_es.len() == 1 {
+                    new_es.pop().unwrap()
+                } else {
+                    Alternate(new_es)
+                }
+            }
+            e => e,
+        }
+    }
+
+    /// Wraps an expression that can't be repeated directly in a
+    /// non-capturing group.
+    fn into_repeat_item(self) -> Expr {
+        if self.can_repeat() {
+            self
+        } else {
+            Group { e: Box::new(self), i: None, name: None }
+        }
+    }
+
+    /// Returns the expression inside this one if this is a non-capturing
+    /// group.
+    fn into_bare(self) -> Expr {
+        match self {
+            Group { e, i: None, name: None } => *e,
+            e => e,
+        }
+    }
+
     /// Returns a set of literal prefixes extracted from this expression.
     pub fn prefixes(&self) -> Literals {
         let mut lits = Literals::empty();
@@ -513,6 +975,500 @@ impl Expr {
         lits
     }
 
+    /// Returns an iterator over this expression and all of its
+    /// sub-expressions in pre-order.
+    pub fn iter_preorder(&self) -> PreorderIter {
+        PreorderIter::new(self)
+    }
+
+    /// Returns an iterator over every literal character in this expression,
+    /// in the order they appear.
+    ///
+    /// Character classes contribute the endpoints of their ranges, so
+    /// `a[b-d]e` yields `a`, `b`, `d` and `e`. A range of a single character
+    /// contributes it once. Note that classes are in canonical form, so a
+    /// negated class yields the endpoints of its complement. Byte literals
+    /// and byte classes are skipped.
+    pub fn literal_chars(&self) -> vec::IntoIter<char> {
+        let mut chars = vec![];
+        for e in self.iter_preorder() {
+            match *e {
+                Literal { chars: ref cs, .. } => chars.extend(cs),
+                Class(ref cls) => {
+                    for r in cls.iter() {
+                        chars.push(r.start);
+                        if r.end != r.start {
+                            chars.push(r.end);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        chars.into_iter()
+    }
+
+    /// Returns the number of capturing groups in this expression.
+    ///
+    /// This counts both named and unnamed capturing groups, but not
+    /// non-capturing groups or the implicit group for the entire match.
+    pub fn capture_count(&self) -> usize {
+        self.iter_preorder().filter(|e| match **e {
+            Group { i: Some(_), .. } => true,
+            _ => false,
+        }).count()
+    }
+
+    /// Returns the name of every capturing group in this expression, or
+    /// `None` for groups without a name.
+    ///
+    /// The names are ordered by group index, which is the order of the
+    /// groups' opening parentheses. The first element corresponds to group
+    /// `1`, since the implicit group `0` for the entire match never has a
+    /// name.
+    pub fn capture_names(&self) -> Vec<Option<&str>> {
+        let mut groups: Vec<(usize, Option<&str>)> = self.iter_preorder()
+            .filter_map(|e| match *e {
+                Group { i: Some(i), ref name, .. } => {
+                    Some((i, name.as_ref().map(|n| &**n)))
+                }
+                _ => None,
+            })
+            .collect();
+        groups.sort_by_key(|&(i, _)| i);
+        groups.into_iter().map(|(_, name)| name).collect()
+    }
+
+    /// Returns the first capture name used by more than one group, along
+    /// with the indices of the first two groups that use it.
+    ///
+    /// Groups are visited in the order of their opening parentheses, and the
+    /// name reported is the one whose second use comes first. The parser
+    /// rejects duplicate names, so this only finds them in expressions that
+    /// were built or combined by hand.
+    pub fn duplicate_capture_name(&self) -> Option<(&str, usize, usize)> {
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for e in self.iter_preorder() {
+            if let Group { i: Some(i), name: Some(ref name), .. } = *e {
+                if let Some(&first) = seen.get(&**name) {
+                    return Some((name, first, i));
+                }
+                seen.insert(name, i);
+            }
+        }
+        None
+    }
+
+    /// Returns a railroad diagram description of this expression.
+    ///
+    /// See `RailroadNode` for details on how an expression is projected.
+    pub fn to_railroad(&self) -> RailroadNode {
+        RailroadNode::from_expr(self)
+    }
+
+    /// Returns an indented tree dump of this expression for debugging.
+    ///
+    /// Each expression is printed on its own line, indented by two spaces
+    /// for every level of nesting. Literals and classes are followed by
+    /// their `Display` form.
+    pub fn tree_string(&self) -> String {
+        visitor::TreePrinter::print(self)
+    }
+
+    /// Returns a Graphviz DOT digraph of this expression for debugging.
+    ///
+    /// Every expression becomes a node, labeled the same way as in
+    /// `tree_string`, with an edge to each of its sub-expressions. Nodes are
+    /// named `n0`, `n1`, ... in pre-order. The output can be rendered with,
+    /// e.g., `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        visitor::DotPrinter::print(self)
+    }
+
+    /// Returns a JSON representation of this expression.
+    ///
+    /// Each expression is written as an object with a `"type"` key, e.g.,
+    /// `{"type":"literal","value":"a","casei":false}`. Sub-expressions are
+    /// nested under an `"expr"` key, or an `"exprs"` key for concatenations
+    /// and alternations. This is meant for exporting expressions to other
+    /// tools. There is no way to parse the JSON back into an `Expr`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        json::write_expr(&mut json, self).unwrap();
+        json
+    }
+
+    /// Returns this expression printed on a single line, suitable for logs.
+    ///
+    /// This is the same as the `Display` output, except that control
+    /// characters and whitespace other than a plain space are replaced by
+    /// escape sequences, e.g., a new line becomes `\n`. The result can still
+    /// be parsed back into an equivalent expression.
+    pub fn to_debug_string(&self) -> String {
+        let mut s = String::new();
+        for c in self.to_string().chars() {
+            match c {
+                '\x07' => s.push_str(r"\a"),
+                '\t' => s.push_str(r"\t"),
+                '\n' => s.push_str(r"\n"),
+                '\x0B' => s.push_str(r"\v"),
+                '\x0C' => s.push_str(r"\f"),
+                '\r' => s.push_str(r"\r"),
+                ' ' => s.push(' '),
+                c if c.is_control() || c.is_whitespace() => {
+                    s.push_str(&format!(r"\x{{{:X}}}", c as u32));
+                }
+                c => s.push(c),
+            }
+        }
+        s
+    }
+
+    /// Returns a heuristic measure of the syntactic complexity of this
+    /// expression.
+    ///
+    /// This is useful for picking the simplest among several equivalent
+    /// expressions. Literal characters are cheap, while groups, repetitions
+    /// and alternations cost more. The exact weights are not part of the
+    /// API and may change; only compare weights computed by the same version
+    /// of this crate.
+    pub fn weight(&self) -> u64 {
+        let mut weight = 0;
+        let mut stack = vec![self];
+        while let Some(e) = stack.pop() {
+            weight += match *e {
+                Empty => 0,
+                Literal { ref chars, .. } => chars.len() as u64,
+                LiteralBytes { ref bytes, .. } => bytes.len() as u64,
+                AnyChar | AnyCharNoNL | AnyByte | AnyByteNoNL => 1,
+                Class(_) | ClassBytes(_) => 2,
+                StartLine | EndLine | StartText | EndText
+                | WordBoundary | NotWordBoundary
+                | WordBoundaryAscii | NotWordBoundaryAscii => 1,
+                Group { ref e, .. } => {
+                    stack.push(e);
+                    2
+                }
+                Repeat { ref e, .. } => {
+                    stack.push(e);
+                    4
+                }
+                Concat(ref es) => {
+                    stack.extend(es);
+                    0
+                }
+                Alternate(ref es) => {
+                    stack.extend(es);
+                    4 * es.len() as u64
+                }
+            };
+        }
+        weight
+    }
+
+    /// Returns the literal text that every match of this expression starts
+    /// with, as far as can be told from the literals at its beginning.
+    ///
+    /// This collects case sensitive literal characters from the start of
+    /// the expression, skipping `^` (when it means the start of the text),
+    /// and stops at the first expression of any other kind. Groups aren't
+    /// looked into. `Literals` offers a more thorough analysis.
+    pub fn literal_prefix(&self) -> String {
+        let mut prefix = String::new();
+        match *self {
+            Literal { ref chars, casei: false } => prefix.extend(chars),
+            Concat(ref es) => {
+                for e in es {
+                    match *e {
+                        StartText => {}
+                        Literal { ref chars, casei: false } => {
+                            prefix.extend(chars);
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            _ => {}
+        }
+        prefix
+    }
+
+    /// Returns the string this expression matches if it matches exactly
+    /// one string.
+    ///
+    /// This is the case when the expression is a case sensitive literal, a
+    /// concatenation of them or empty, e.g., `a\.b` is the literal `a.b`.
+    /// Any other expression, including case insensitive and byte literals,
+    /// results in `None`. This can be used to search for a pattern with a
+    /// plain substring search instead.
+    pub fn as_literal_string(&self) -> Option<String> {
+        match *self {
+            Empty => Some(String::new()),
+            Literal { ref chars, casei: false } => {
+                Some(chars.iter().cloned().collect())
+            }
+            Concat(ref es) => {
+                let mut s = String::new();
+                for e in es {
+                    match *e {
+                        Literal { ref chars, casei: false } => s.extend(chars),
+                        _ => return None,
+                    }
+                }
+                Some(s)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the maximum nesting depth of this expression.
+    ///
+    /// Groups, repetitions, concatenations and alternations each add one
+    /// layer around the expressions inside them. Everything else, including
+    /// character classes, is a leaf with depth `0`. For example, `a(b|c)*`
+    /// has depth `4`.
+    ///
+    /// This doesn't use recursion, so it is safe to call on arbitrarily deep
+    /// expressions.
+    pub fn depth(&self) -> u32 {
+        let mut deepest = 0;
+        let mut stack = vec![(self, 0)];
+        while let Some((e, depth)) = stack.pop() {
+            deepest = max(deepest, depth);
+            match *e {
+                Group { ref e, .. } | Repeat { ref e, .. } => {
+                    stack.push((e, depth + 1));
+                }
+                Concat(ref es) | Alternate(ref es) => {
+                    stack.extend(es.iter().map(|e| (e, depth + 1)));
+                }
+                _ => {}
+            }
+        }
+        deepest
+    }
+
+    /// Returns every counted repetition in this expression whose bound
+    /// exceeds `limit`, along with that bound.
+    ///
+    /// The bound of `{m,n}` is `n`, and the bound of `{m}` or `{m,}` is `m`.
+    /// Large bounds are legal, but they expand into correspondingly large
+    /// programs, so this can be used to warn about them before compiling.
+    /// Repetitions are returned in the order they appear in the expression.
+    pub fn large_repetitions(&self, limit: u32) -> Vec<(&Expr, u32)> {
+        let mut found = vec![];
+        let mut stack = vec![self];
+        while let Some(expr) = stack.pop() {
+            match *expr {
+                Group { ref e, .. } => stack.push(e),
+                Repeat { ref e, r, .. } => {
+                    if let Range { min, max } = r {
+                        let bound = max.unwrap_or(min);
+                        if bound > limit {
+                            found.push((expr, bound));
+                        }
+                    }
+                    stack.push(e);
+                }
+                Concat(ref es) | Alternate(ref es) => {
+                    stack.extend(es.iter().rev());
+                }
+                _ => {}
+            }
+        }
+        found
+    }
+
+    /// Returns the pairs of branches of this alternation that are identical.
+    ///
+    /// Each pair `(i, j)` with `i < j` holds the indices of two branches that
+    /// are equal, so the later branch can never contribute a match. Pairs are
+    /// sorted. If this expression isn't an alternation, the result is empty.
+    /// Nested alternations aren't searched.
+    pub fn redundant_arms(&self) -> Vec<(usize, usize)> {
+        let es = match *self {
+            Alternate(ref es) => es,
+            _ => return vec![],
+        };
+        let mut pairs = vec![];
+        for (i, e1) in es.iter().enumerate() {
+            for (j, e2) in es.iter().enumerate().skip(i + 1) {
+                if e1 == e2 {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Returns true if this expression contains a pattern that commonly
+    /// makes backtracking engines take exponential time.
+    ///
+    /// This is a heuristic and not a proof either way. It looks for an
+    /// unbounded repetition whose body can match the same text in more than
+    /// one way: the body is itself an unbounded repetition, as in `(a+)+` or
+    /// `(.*)*`, a concatenation of such a repetition with only nullable
+    /// expressions, as in `(a+b?)*`, or an alternation with identical
+    /// branches, as in `(a|a)*`. Non-capturing and capturing groups are
+    /// looked through.
+    pub fn has_nested_quantifier_risk(&self) -> bool {
+        fn unbounded_repeat(e: &Expr) -> bool {
+            match *e {
+                Group { ref e, .. } => unbounded_repeat(e),
+                Repeat { r, .. } => r.bounds().1.is_none(),
+                _ => false,
+            }
+        }
+        fn ambiguous_body(e: &Expr) -> bool {
+            match *e {
+                Group { ref e, .. } => ambiguous_body(e),
+                Repeat { r, .. } => r.bounds().1.is_none(),
+                Concat(ref es) => {
+                    es.iter().enumerate().any(|(i, e)| {
+                        unbounded_repeat(e)
+                        && es.iter().enumerate()
+                             .all(|(j, e)| i == j || e.is_nullable())
+                    })
+                }
+                Alternate(_) => !e.redundant_arms().is_empty(),
+                _ => false,
+            }
+        }
+        self.iter_preorder().any(|e| match *e {
+            Repeat { ref e, r, .. } => {
+                r.bounds().1.is_none() && ambiguous_body(e)
+            }
+            _ => false,
+        })
+    }
+
+    /// Checks that every counted repetition in this expression has a
+    /// minimum that doesn't exceed its maximum.
+    ///
+    /// The parser already rejects ranges like `a{3,2}`, so this is only
+    /// useful for expressions that were built or rewritten by hand. The
+    /// first invalid repetition found results in an `InvalidRepeatRange`
+    /// error. Since there is no pattern to point into, its position is `0`.
+    pub fn validate_repetitions(&self) -> Result<()> {
+        for e in self.iter_preorder() {
+            if let Repeat { r: Range { min, max: Some(max) }, .. } = *e {
+                if min > max {
+                    return Err(Error {
+                        pos: 0,
+                        surround: "".to_owned(),
+                        kind: ErrorKind::InvalidRepeatRange {
+                            min: min,
+                            max: max,
+                        },
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if and only if this expression can match the empty
+    /// string.
+    ///
+    /// Assertions such as `^` and `\b` don't consume any text, so they are
+    /// considered nullable even though they may fail to match at a given
+    /// position.
+    pub fn is_nullable(&self) -> bool {
+        match *self {
+            Empty
+            | StartLine | EndLine | StartText | EndText
+            | WordBoundary | NotWordBoundary
+            | WordBoundaryAscii | NotWordBoundaryAscii => true,
+            Literal{..} | LiteralBytes{..}
+            | AnyChar | AnyCharNoNL | AnyByte | AnyByteNoNL
+            | Class(_) | ClassBytes(_) => false,
+            Group { ref e, .. } => e.is_nullable(),
+            Repeat { ref e, r, .. } => r.matches_empty() || e.is_nullable(),
+            Concat(ref es) => es.iter().all(|e| e.is_nullable()),
+            Alternate(ref es) => es.iter().any(|e| e.is_nullable()),
+        }
+    }
+
+    /// Returns the minimum number of characters that any match of this
+    /// expression must consume.
+    ///
+    /// For expressions that match bytes, such as `(?-u:\xFF)`, each byte
+    /// counts as one. Assertions consume nothing, and the result saturates
+    /// at `usize::MAX` instead of overflowing. A text shorter than this can
+    /// never contain a match.
+    pub fn min_len(&self) -> usize {
+        match *self {
+            Empty
+            | StartLine | EndLine | StartText | EndText
+            | WordBoundary | NotWordBoundary
+            | WordBoundaryAscii | NotWordBoundaryAscii => 0,
+            Literal { ref chars, .. } => chars.len(),
+            LiteralBytes { ref bytes, .. } => bytes.len(),
+            AnyChar | AnyCharNoNL | AnyByte | AnyByteNoNL
+            | Class(_) | ClassBytes(_) => 1,
+            Group { ref e, .. } => e.min_len(),
+            Repeat { ref e, r, .. } => {
+                let min = match r {
+                    ZeroOrOne | ZeroOrMore => 0,
+                    OneOrMore => 1,
+                    Range { min, .. } => min as usize,
+                };
+                e.min_len().saturating_mul(min)
+            }
+            Concat(ref es) => {
+                es.iter().fold(0, |sum, e| sum.saturating_add(e.min_len()))
+            }
+            Alternate(ref es) => {
+                es.iter().map(|e| e.min_len()).min().unwrap_or(0)
+            }
+        }
+    }
+
+    /// Returns this expression anchored to the beginning of text.
+    ///
+    /// A `StartText` assertion is prepended to the expression, adding a
+    /// concatenation if necessary. An alternation is first wrapped in a
+    /// non-capturing group so that the anchor applies to every branch.
+    pub fn anchored_start(self) -> Expr {
+        match self {
+            Empty => StartText,
+            Concat(mut es) => {
+                es.insert(0, StartText);
+                Concat(es)
+            }
+            e => Concat(vec![StartText, e.into_concat_item()]),
+        }
+    }
+
+    /// Returns this expression anchored to the end of text.
+    ///
+    /// An `EndText` assertion is appended to the expression, adding a
+    /// concatenation if necessary. An alternation is first wrapped in a
+    /// non-capturing group so that the anchor applies to every branch.
+    pub fn anchored_end(self) -> Expr {
+        match self {
+            Empty => EndText,
+            Concat(mut es) => {
+                es.push(EndText);
+                Concat(es)
+            }
+            e => Concat(vec![e.into_concat_item(), EndText]),
+        }
+    }
+
+    /// Wraps an alternation in a non-capturing group so that it can be used
+    /// as an element of a concatenation.
+    fn into_concat_item(self) -> Expr {
+        match self {
+            Alternate(es) => Group {
+                e: Box::new(Alternate(es)),
+                i: None,
+                name: None,
+            },
+            e => e,
+        }
+    }
+
     /// Returns true if and only if the expression is required to match from
     /// the beginning of text.
     pub fn is_anchored_start(&self) -> bool {
@@ -573,6 +1529,34 @@ impl Expr {
         }
     }
 
+    /// Returns true if and only if the expression is a word boundary
+    /// assertion (`\b` or `\B`), in either Unicode or ASCII mode.
+    pub fn is_word_boundary(&self) -> bool {
+        match *self {
+            WordBoundary | NotWordBoundary
+            | WordBoundaryAscii | NotWordBoundaryAscii => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if and only if the expression is an assertion on the
+    /// beginning or end of text.
+    pub fn is_text_anchor(&self) -> bool {
+        match *self {
+            StartText | EndText => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if and only if the expression is an assertion on the
+    /// beginning or end of a line.
+    pub fn is_line_anchor(&self) -> bool {
+        match *self {
+            StartLine | EndLine => true,
+            _ => false,
+        }
+    }
+
     /// Returns true if and only if the expression contains sub-expressions
     /// that can match arbitrary bytes.
     pub fn has_bytes(&self) -> bool {
@@ -613,6 +1597,48 @@ impl CharClass {
         CharClass { ranges: ranges }
     }
 
+    /// Create a new class that matches exactly the given characters.
+    ///
+    /// The characters may be given in any order and may contain duplicates.
+    /// Adjacent characters are merged into ranges, so the resulting class is
+    /// in canonical form.
+    pub fn from_chars(chars: &[char]) -> CharClass {
+        let ranges = chars.iter().map(|&c| ClassRange::one(c)).collect();
+        CharClass::new(ranges).canonicalize()
+    }
+
+    /// Adds the characters in the inclusive range `start..end` to this
+    /// class, keeping it in canonical form.
+    ///
+    /// If `end < start`, then the two values are swapped.
+    pub fn push_range(&mut self, start: char, end: char) {
+        let mut ranges = mem::replace(&mut self.ranges, vec![]);
+        ranges.push(ClassRange::new(start, end));
+        *self = CharClass::new(ranges).canonicalize();
+    }
+
+    /// Returns the expansion of the Perl character class `\name` into
+    /// explicit ranges.
+    ///
+    /// `name` must be one of `d`, `s`, `w`, or their negated counterparts
+    /// `D`, `S` and `W`. Otherwise, `None` is returned. When `unicode` is
+    /// false, the ASCII-only definitions are used, i.e., `[0-9]`,
+    /// `[\t\n\v\f\r ]` and `[0-9A-Za-z_]`.
+    pub fn perl(name: char, unicode: bool) -> Option<CharClass> {
+        parser::perl_class(name, unicode).map(|(cls, negate)| {
+            if negate { cls.negate() } else { cls.canonicalize() }
+        })
+    }
+
+    /// Returns the ranges in this class as inclusive `(start, end)` pairs.
+    ///
+    /// Classes produced by the parser have already had their negation,
+    /// nested classes and intersections applied, so the pairs are sorted and
+    /// non-overlapping.
+    pub fn to_ranges(&self) -> Vec<(char, char)> {
+        self.iter().map(|r| (r.start, r.end)).collect()
+    }
+
     /// Create an empty class.
     fn empty() -> CharClass {
         CharClass::new(Vec::new())
@@ -1459,6 +2485,13 @@ pub enum ErrorKind {
     /// The work around for end users is to escape the character included in
     /// this error message.
     UnsupportedClassChar(char),
+    /// Look-around assertions are not supported. e.g., `(?<!a)b`.
+    ///
+    /// The kind of look-around that was used is included.
+    UnsupportedLookAround(LookAroundKind),
+    /// Character classes were nested more deeply than the configured limit,
+    /// which is included. e.g., `[[[a]]]` with a limit of `2`.
+    ClassNestLimitExceeded(usize),
     /// Hints that destructuring should not be exhaustive.
     ///
     /// This enum may grow additional variants, so this makes sure clients
@@ -1468,6 +2501,19 @@ pub enum ErrorKind {
     __Nonexhaustive,
 }
 
+/// The kind of an unsupported look-around assertion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LookAroundKind {
+    /// A look-ahead, `(?=a)`.
+    Ahead,
+    /// A negative look-ahead, `(?!a)`.
+    NegAhead,
+    /// A look-behind, `(?<=a)`.
+    Behind,
+    /// A negative look-behind, `(?<!a)`.
+    NegBehind,
+}
+
 impl Error {
     /// Returns an approximate *character* offset at which the error occurred.
     ///
@@ -1482,9 +2528,104 @@ impl Error {
     pub fn kind(&self) -> &ErrorKind {
         &self.kind
     }
+
+    /// Returns the stable numeric code of this error's kind.
+    ///
+    /// This is a shortcut for `self.kind().code()`.
+    pub fn code(&self) -> u16 {
+        self.kind.code()
+    }
+
+    /// Renders this error followed by the line of `pattern` that contains
+    /// it, with a caret beneath the character at which the error occurred.
+    ///
+    /// `pattern` should be the regex that produced this error. Columns are
+    /// counted in characters, so the caret lines up when every character is
+    /// displayed with the same width.
+    pub fn snippet(&self, pattern: &str) -> String {
+        let mut line = "";
+        let mut line_start = 0;
+        for l in pattern.split('\n') {
+            line = l;
+            let len = l.chars().count();
+            if self.pos <= line_start + len {
+                break;
+            }
+            line_start += len + 1;
+        }
+        let col = min(
+            self.pos.saturating_sub(line_start), line.chars().count());
+        let pad: String = repeat(' ').take(col).collect();
+        format!("{}\n{}\n{}^", self, line, pad)
+    }
 }
 
 impl ErrorKind {
+    /// Returns a small number that identifies this kind of error.
+    ///
+    /// Codes are part of the public API and are never reused or changed.
+    /// Every kind has a distinct code, and kinds added in the future get
+    /// new codes. The data attached to a kind doesn't affect its code.
+    pub fn code(&self) -> u16 {
+        use ErrorKind::*;
+        match *self {
+            DoubleFlagNegation => 1,
+            DuplicateCaptureName(_) => 2,
+            EmptyAlternate => 3,
+            EmptyCaptureName => 4,
+            EmptyFlagNegation => 5,
+            EmptyGroup => 6,
+            InvalidBase10(_) => 7,
+            InvalidBase16(_) => 8,
+            InvalidCaptureName(_) => 9,
+            InvalidClassRange{..} => 10,
+            InvalidClassEscape(_) => 11,
+            InvalidRepeatRange{..} => 12,
+            InvalidScalarValue(_) => 13,
+            MissingBase10 => 14,
+            RepeaterExpectsExpr => 15,
+            RepeaterUnexpectedExpr(_) => 16,
+            UnclosedCaptureName(_) => 17,
+            UnclosedHex => 18,
+            UnclosedParen => 19,
+            UnclosedRepeat => 20,
+            UnclosedUnicodeName => 21,
+            UnexpectedClassEof => 22,
+            UnexpectedEscapeEof => 23,
+            UnexpectedFlagEof => 24,
+            UnexpectedTwoDigitHexEof => 25,
+            UnopenedParen => 26,
+            UnrecognizedEscape(_) => 27,
+            UnrecognizedFlag(_) => 28,
+            UnrecognizedUnicodeClass(_) => 29,
+            StackExhausted => 30,
+            FlagNotAllowed(_) => 31,
+            UnicodeNotAllowed => 32,
+            InvalidUtf8 => 33,
+            EmptyClass => 34,
+            UnsupportedClassChar(_) => 35,
+            UnsupportedLookAround(_) => 36,
+            ClassNestLimitExceeded(_) => 37,
+            __Nonexhaustive => unreachable!(),
+        }
+    }
+
+    /// Returns a likely correction for the input that caused this error, if
+    /// one is known.
+    ///
+    /// Currently, only `UnrecognizedUnicodeClass` errors have suggestions.
+    /// The suggestion is the name of a known Unicode class that is within a
+    /// small edit distance of the unrecognized name. e.g., `Greeek` suggests
+    /// `Greek`.
+    pub fn suggest(&self) -> Option<String> {
+        match *self {
+            ErrorKind::UnrecognizedUnicodeClass(ref name) => {
+                suggest_unicode_class(name).map(|s| s.to_owned())
+            }
+            _ => None,
+        }
+    }
+
     fn description(&self) -> &str {
         use ErrorKind::*;
         match *self {
@@ -1523,6 +2664,8 @@ impl ErrorKind {
             InvalidUtf8 => "matching arbitrary bytes is not allowed",
             EmptyClass => "empty character class",
             UnsupportedClassChar(_) => "unsupported class notation",
+            UnsupportedLookAround(_) => "look-around not supported",
+            ClassNestLimitExceeded(_) => "character class nesting too deep",
             __Nonexhaustive => unreachable!(),
         }
     }
@@ -1635,11 +2778,28 @@ impl fmt::Display for ErrorKind {
             UnsupportedClassChar(c) =>
                 write!(f, "Use of unescaped '{}' in character class is \
                            not allowed.", c),
+            UnsupportedLookAround(kind) =>
+                write!(f, "{} is not supported.", kind),
+            ClassNestLimitExceeded(limit) =>
+                write!(f, "Character classes may not be nested more than {} \
+                           levels deep.", limit),
             __Nonexhaustive => unreachable!(),
         }
     }
 }
 
+impl fmt::Display for LookAroundKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use LookAroundKind::*;
+        match *self {
+            Ahead => write!(f, "look-ahead"),
+            NegAhead => write!(f, "negative look-ahead"),
+            Behind => write!(f, "look-behind"),
+            NegBehind => write!(f, "negative look-behind"),
+        }
+    }
+}
+
 /// The result of binary search on the simple case folding table.
 ///
 /// Note that this binary search is done on the "both" table, such that
@@ -1755,7 +2915,11 @@ mod properties;
 
 #[cfg(test)]
 mod tests {
-    use {CharClass, ClassRange, ByteClass, ByteRange, Expr};
+    use {
+        CharClass, ClassRange, ByteClass, ByteRange, Expr, ExprBuilder,
+        Repeater, escape,
+    };
+    use {ErrorKind, LookAroundKind};
 
     fn class(ranges: &[(char, char)]) -> CharClass {
         let ranges = ranges.iter().cloned()
@@ -1784,6 +2948,25 @@ mod tests {
         assert!(Expr::parse(&format!("{}a{}", open, close)).is_err());
     }
 
+    #[test]
+    fn class_nest_limit() {
+        use std::iter::repeat;
+
+        let open: String = repeat('[').take(1000).collect();
+        let close: String = repeat(']').take(1000).collect();
+        let re = format!("{}a{}", open, close);
+        let err = ExprBuilder::new().nest_limit(50).parse(&re).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ClassNestLimitExceeded(50));
+        assert_eq!(err.position(), 50);
+        assert!(ExprBuilder::new().nest_limit(1000).parse(&re).is_ok());
+
+        let open: String = repeat('[').take(200).collect();
+        let close: String = repeat(']').take(200).collect();
+        assert!(Expr::parse(&format!("{}a{}", open, close)).is_ok());
+        let err = Expr::parse(&format!("[{}a{}]", open, close)).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ClassNestLimitExceeded(200));
+    }
+
     #[test]
     fn anchored_start() {
         assert!(e("^a").is_anchored_start());
@@ -1796,6 +2979,19 @@ mod tests {
         assert!(!e("a|^b").is_anchored_start());
     }
 
+    #[test]
+    fn anchored_text_escapes() {
+        assert!(e(r"\Aabc").is_anchored_start());
+        assert!(!e(r"\Aabc").is_anchored_end());
+        assert!(e(r"abc\z").is_anchored_end());
+        assert!(!e(r"abc\z").is_anchored_start());
+        assert!(e(r"(\Aa|\Ab)").is_anchored_start());
+
+        // In multi-line mode, `^` and `$` only match at line boundaries.
+        assert!(!e("(?m)^abc").is_anchored_start());
+        assert!(!e("(?m)abc$").is_anchored_end());
+    }
+
     #[test]
     fn anchored_end() {
         assert!(e("a$").is_anchored_end());
@@ -1808,6 +3004,482 @@ mod tests {
         assert!(!e("a|b$").is_anchored_end());
     }
 
+    #[test]
+    fn builder_default_flags() {
+        let b = ExprBuilder::new().with_default_flags("imx").unwrap();
+        assert!(b.flags.casei);
+        assert!(b.flags.multi);
+        assert!(b.flags.ignore_space);
+        assert!(!b.flags.dotnl);
+        assert!(!b.flags.swap_greed);
+        assert_eq!(b.parse("a b").unwrap(),
+                   Expr::Literal { chars: vec!['a', 'b'], casei: true });
+
+        let b = ExprBuilder::new().with_default_flags("").unwrap();
+        assert!(!b.flags.casei);
+
+        let err = ExprBuilder::new().with_default_flags("iq").unwrap_err();
+        assert_eq!(err.position(), 1);
+        assert_eq!(err.kind(), &ErrorKind::UnrecognizedFlag('q'));
+    }
+
+    #[test]
+    fn assertion_kinds() {
+        let all = vec![
+            Expr::StartLine, Expr::EndLine,
+            Expr::StartText, Expr::EndText,
+            Expr::WordBoundary, Expr::NotWordBoundary,
+            Expr::WordBoundaryAscii, Expr::NotWordBoundaryAscii,
+        ];
+        let got: Vec<_> = all.iter().map(|e| {
+            (e.is_line_anchor(), e.is_text_anchor(), e.is_word_boundary())
+        }).collect();
+        assert_eq!(got, vec![
+            (true, false, false), (true, false, false),
+            (false, true, false), (false, true, false),
+            (false, false, true), (false, false, true),
+            (false, false, true), (false, false, true),
+        ]);
+
+        assert!(!e("a").is_line_anchor());
+        assert!(!e("a").is_text_anchor());
+        assert!(!e("a").is_word_boundary());
+        assert!(!e(r"(\b)").is_word_boundary());
+    }
+
+    #[test]
+    fn debug_string_single_line() {
+        let expr = e("a\nb[\t ]\u{2028}");
+        let dbg = expr.to_debug_string();
+        assert_eq!(dbg, r"(?u:a\nb)(?u:[\t-\t - ])(?u:\x{2028})");
+        assert!(!dbg.contains('\n'));
+        assert_eq!(e(&dbg), expr);
+
+        let expr = Expr::parse("(?x)a\n b").unwrap();
+        assert_eq!(expr.to_debug_string(), "(?u:ab)");
+    }
+
+    #[test]
+    fn class_perl_ascii() {
+        let d = CharClass::perl('d', false).unwrap();
+        let s = CharClass::perl('s', false).unwrap();
+        let w = CharClass::perl('w', false).unwrap();
+        assert_eq!(d, class(&[('0', '9')]));
+        assert_eq!(s, class(&[('\t', '\r'), (' ', ' ')]));
+        assert_eq!(w, class(&[
+            ('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z'),
+        ]));
+        assert_eq!(CharClass::perl('W', false).unwrap(), w.clone().negate());
+        assert_eq!(CharClass::perl('x', false), None);
+
+        for cls in vec![d, s, w] {
+            for cls in vec![cls.clone(), cls.negate()] {
+                let expr = Expr::Class(cls.clone());
+                assert_eq!(e(&expr.to_string()),
+                           Expr::Class(cls.canonicalize()));
+            }
+        }
+    }
+
+    #[test]
+    fn class_perl_unicode() {
+        assert_eq!(Expr::Class(CharClass::perl('w', true).unwrap()),
+                   e(r"\w"));
+        assert_eq!(Expr::Class(CharClass::perl('S', true).unwrap()),
+                   e(r"\S"));
+    }
+
+    #[test]
+    fn simplify_empty() {
+        use Expr::*;
+
+        let a = || e("a");
+        let b = || e("b");
+        let expr = Concat(vec![Empty, a(), Empty, b(), Empty]);
+        assert_eq!(expr.simplify_empty(), Concat(vec![a(), b()]));
+        assert_eq!(Concat(vec![Empty, a()]).simplify_empty(), a());
+        assert_eq!(Concat(vec![Empty, Empty]).simplify_empty(), Empty);
+
+        let expr = Alternate(vec![Concat(vec![a(), Empty]), Empty]);
+        assert_eq!(expr.simplify_empty(), Alternate(vec![a(), Empty]));
+
+        let expr = Group {
+            e: Box::new(Concat(vec![Empty, a()])),
+            i: Some(1),
+            name: None,
+        };
+        assert_eq!(expr.simplify_empty(), e("(a)"));
+
+        for re in &["", "a(b|c)*d", r"^(?P<x>\w+)?$"] {
+            assert_eq!(e(re).simplify_empty(), e(re));
+        }
+    }
+
+    #[test]
+    fn weight() {
+        assert!(e("a").weight() < e("(?:a|a)").weight());
+        assert!(e("ab").weight() < e("a(b)").weight());
+        assert!(e("[ab]").weight() < e("a|b").weight());
+        assert!(e("aa").weight() < e("a{2}").weight());
+        assert_eq!(Expr::Empty.weight(), 0);
+    }
+
+    #[test]
+    fn anchored_variants() {
+        let tests = vec![
+            ("a", "^(?u:a)", "(?u:a)$"),
+            ("ab+", "^(?u:a)(?u:b)+", "(?u:a)(?u:b)+$"),
+            ("a|b", "^(?:(?u:a)|(?u:b))", "(?:(?u:a)|(?u:b))$"),
+            ("(a|b)", "^((?u:a)|(?u:b))", "((?u:a)|(?u:b))$"),
+        ];
+        for (re, start, end) in tests {
+            let anchored = e(re).anchored_start();
+            assert_eq!(anchored.to_string(), start);
+            assert_eq!(e(start), anchored);
+            assert!(anchored.is_anchored_start());
+
+            let anchored = e(re).anchored_end();
+            assert_eq!(anchored.to_string(), end);
+            assert_eq!(e(end), anchored);
+            assert!(anchored.is_anchored_end());
+        }
+        assert_eq!(Expr::Empty.anchored_start(), Expr::StartText);
+        assert_eq!(Expr::Empty.anchored_end(), Expr::EndText);
+    }
+
+    #[test]
+    fn nested_quantifier_risk() {
+        for re in &["(a+)+", "(.*)*", "(?:a|a)*", "(a*b*)*", "x(?:y{2,})*z",
+                    "a|((c+)d?)+"] {
+            assert!(e(re).has_nested_quantifier_risk(), "{}", re);
+        }
+        for re in &["a+b+", "(ab+)+", "(a+){3}", "(a|b)*", "(a?)+", "a*"] {
+            assert!(!e(re).has_nested_quantifier_risk(), "{}", re);
+        }
+    }
+
+    #[test]
+    fn redundant_arms() {
+        assert_eq!(e("a|b|a").redundant_arms(), vec![(0, 2)]);
+        assert_eq!(e("a|b|c").redundant_arms(), vec![]);
+        assert_eq!(e("x+|[ab]|x+|x+").redundant_arms(),
+                   vec![(0, 2), (0, 3), (2, 3)]);
+        assert_eq!(e("x+|x+?").redundant_arms(), vec![]);
+        assert_eq!(e("(?i)a|(?-i)a").redundant_arms(), vec![]);
+        assert_eq!(e("aa").redundant_arms(), vec![]);
+    }
+
+    #[test]
+    fn validate_repetitions() {
+        assert!(e("a{2,3}").validate_repetitions().is_ok());
+        assert!(e("(a{3}|b{3,})*").validate_repetitions().is_ok());
+        assert!(Expr::parse("a{3,2}").is_err());
+
+        let bad = Expr::Repeat {
+            e: Box::new(e("a")),
+            r: Repeater::Range { min: 3, max: Some(2) },
+            greedy: true,
+        };
+        let expr = Expr::Concat(vec![e("b"), Expr::Group {
+            e: Box::new(bad),
+            i: Some(1),
+            name: None,
+        }]);
+        let err = expr.validate_repetitions().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidRepeatRange {
+            min: 3,
+            max: 2,
+        });
+    }
+
+    #[test]
+    fn is_nullable() {
+        assert!(e("a*").is_nullable());
+        assert!(!e("a+").is_nullable());
+        assert!(e("(a|b?)").is_nullable());
+        assert!(!e("ab").is_nullable());
+        assert!(e(r"^\b$").is_nullable());
+        assert!(e("(a?b*){2,}").is_nullable());
+        assert!(!e("a{0,3}b").is_nullable());
+        // The parser rejects empty alternates, so build `(a|)` by hand.
+        let alt = Expr::Group {
+            e: Box::new(Expr::Alternate(vec![
+                Expr::Literal { chars: vec!['a'], casei: false },
+                Expr::Empty,
+            ])),
+            i: Some(1),
+            name: None,
+        };
+        assert!(alt.is_nullable());
+    }
+
+    #[test]
+    fn min_len() {
+        assert_eq!(e("a{3,}b").min_len(), 4);
+        assert_eq!(e("(a|bc)").min_len(), 1);
+        assert_eq!(e("a{0,5}").min_len(), 0);
+        assert_eq!(e("(ab){3}").min_len(), 6);
+        assert_eq!(e(r"^\w+?\b.*$").min_len(), 1);
+        assert_eq!(e("(?i)δx?").min_len(), 1);
+        assert_eq!(e("((a{1000}){1000}){1000}").min_len(), 1_000_000_000);
+    }
+
+    #[test]
+    fn large_repetitions() {
+        let expr = e("a{5}b{1000}(c{2,3000}|d{2000,})e{10,20}?(f{4096})*");
+        let got: Vec<_> = expr.large_repetitions(100)
+            .into_iter()
+            .map(|(e, bound)| (e.to_string(), bound))
+            .collect();
+        assert_eq!(got, vec![
+            ("(?u:b){1000}".to_owned(), 1000),
+            ("(?u:c){2, 3000}".to_owned(), 3000),
+            ("(?u:d){2000,}".to_owned(), 2000),
+            ("(?u:f){4096}".to_owned(), 4096),
+        ]);
+        assert!(expr.large_repetitions(5000).is_empty());
+        assert_eq!(expr.large_repetitions(4095).len(), 1);
+    }
+
+    #[test]
+    fn as_literal_string() {
+        assert_eq!(e(r"a\.b").as_literal_string(), Some("a.b".to_owned()));
+        assert_eq!(e(&escape("(δ)*")).as_literal_string(),
+                   Some("(δ)*".to_owned()));
+        assert_eq!(e("").as_literal_string(), Some("".to_owned()));
+        assert_eq!(e("a.b").as_literal_string(), None);
+        assert_eq!(e("(?i)ab").as_literal_string(), None);
+        assert_eq!(e("a(?i)b").as_literal_string(), None);
+        assert_eq!(e("a(b)").as_literal_string(), None);
+        assert_eq!(e("^ab").as_literal_string(), None);
+        assert_eq!(e("ab+").as_literal_string(), None);
+        assert_eq!(e("a|b").as_literal_string(), None);
+    }
+
+    #[test]
+    fn escape_roundtrip() {
+        assert_eq!(escape("a.b*c"), r"a\.b\*c");
+        assert_eq!(e(&escape("a.b*c")), Expr::Literal {
+            chars: vec!['a', '.', 'b', '*', 'c'],
+            casei: false,
+        });
+
+        let meta = r"\.+*?()|[]{}^$#&-~";
+        assert_eq!(e(&escape(meta)), Expr::Literal {
+            chars: meta.chars().collect(),
+            casei: false,
+        });
+    }
+
+    #[test]
+    fn build_many() {
+        use Expr::*;
+
+        let builder = ExprBuilder::new().case_insensitive(true);
+        let mut parser = builder.build_many();
+        for re in &["a", "(?P<x>b)(c)", r"\d+|[a-z]", "(?-i)x"] {
+            let want = builder.clone().parse(re);
+            assert_eq!(parser.parse(re), want);
+        }
+        assert_eq!(parser.parse("(a").unwrap_err().kind(),
+                   &ErrorKind::UnclosedParen);
+        assert_eq!(parser.parse("(?P<x>a)(?P<y>b)").unwrap(), Concat(vec![
+            Group {
+                e: Box::new(Literal { chars: vec!['a'], casei: true }),
+                i: Some(1),
+                name: Some("x".to_owned()),
+            },
+            Group {
+                e: Box::new(Literal { chars: vec!['b'], casei: true }),
+                i: Some(2),
+                name: Some("y".to_owned()),
+            },
+        ]));
+    }
+
+    #[test]
+    fn build_many_reset() {
+        use std::iter::repeat;
+
+        let builder = ExprBuilder::new().case_insensitive(true);
+        let mut parser = builder.build_many();
+        let big: String = repeat("(a)").take(1000).collect();
+        assert!(parser.parse(&big).is_ok());
+        parser.reset();
+        for re in &["(?P<x>b)(c)", r"\d+|[a-z]"] {
+            assert_eq!(parser.parse(re), builder.clone().parse(re));
+            parser.reset();
+        }
+    }
+
+    #[test]
+    fn repeater_bounds() {
+        fn bounds(re: &str) -> (u32, Option<u32>) {
+            match e(re) {
+                Expr::Repeat { r, .. } => r.bounds(),
+                e => panic!("expected a repetition, got {:?}", e),
+            }
+        }
+        assert_eq!(bounds("a?"), (0, Some(1)));
+        assert_eq!(bounds("a*"), (0, None));
+        assert_eq!(bounds("a+"), (1, None));
+        assert_eq!(bounds("a{3}"), (3, Some(3)));
+        assert_eq!(bounds("a{3,}"), (3, None));
+        assert_eq!(bounds("a{0,}"), (0, None));
+        assert_eq!(bounds("a{2,5}"), (2, Some(5)));
+    }
+
+    #[test]
+    fn flatten() {
+        use Expr::*;
+
+        let lit = |c| Literal { chars: vec![c], casei: false };
+        let nested = Concat(vec![
+            Concat(vec![lit('a'), Concat(vec![lit('b')])]),
+            lit('c'),
+        ]);
+        let before = nested.to_string();
+        let flat = nested.flatten();
+        assert_eq!(flat, Concat(vec![lit('a'), lit('b'), lit('c')]));
+        assert_eq!(flat.to_string(), before);
+
+        let nested = Alternate(vec![
+            Alternate(vec![lit('a'), lit('b')]),
+            Concat(vec![Concat(vec![lit('c')]), lit('d')]),
+        ]);
+        assert_eq!(nested.flatten(), Alternate(vec![
+            lit('a'), lit('b'), Concat(vec![lit('c'), lit('d')]),
+        ]));
+
+        // A group keeps an alternation apart from its parent.
+        for re in &["a(b|c)d", "(?:a|b)|c"] {
+            assert_eq!(e(re).flatten(), e(re));
+        }
+    }
+
+    #[test]
+    fn simplify_repetitions() {
+        fn simp(re: &str) -> String {
+            e(re).simplify_repetitions().to_string()
+        }
+
+        assert_eq!(simp("(?:a*)*"), "(?u:a)*");
+        assert_eq!(simp("(?:a*)+"), "(?u:a)*");
+        assert_eq!(simp("(?:a+)*"), "(?u:a)*");
+        assert_eq!(simp("(?:a+)+"), "(?u:a)+");
+        assert_eq!(simp("(?:a+?)+?"), "(?u:a)+?");
+        assert_eq!(simp("(?:(?:ab+)+)+"), "(?:(?u:a)(?u:b)+)+");
+        assert_eq!(simp("(?:(?:a+)*)+"), "(?u:a)*");
+        assert_eq!(simp("x(?:[0-9]+)*y"), "(?u:x)(?u:[0-9])*(?u:y)");
+
+        // Mixed greediness, capturing groups and other repetition
+        // operators are left alone.
+        assert_eq!(simp("(?:a*?)*"), "(?:(?u:a)*?)*");
+        assert_eq!(simp("(a*)*"), "((?u:a)*)*");
+        assert_eq!(simp("(?:a?)*"), "(?:(?u:a)?)*");
+        assert_eq!(simp("(?:a{2})*"), "(?:(?u:a){2})*");
+    }
+
+    #[test]
+    fn canonical_string() {
+        fn canon(re: &str) -> String { e(re).to_canonical_string() }
+
+        assert_eq!(canon("a{1}"), "(?u:a)");
+        assert_eq!(canon("(?:a)"), "(?u:a)");
+        assert_eq!(canon(r"\x61"), "(?u:a)");
+        assert_eq!(canon("(?:a.){1}b"), canon("a.b"));
+        assert_eq!(canon("x(?:ab){1}y"), canon("xaby"));
+        assert_eq!(canon("(?:(?:a|b)|c)"), canon("a|b|c"));
+        assert_eq!(canon("(?:a*)b"), canon("a*b"));
+        assert_eq!(canon("(?:a|b)c"), "(?:(?u:a)|(?u:b))(?u:c)");
+        assert_eq!(canon("(?:a.)*"), "(?:(?u:a)(?u:.))*");
+        assert_eq!(canon("(a){1}"), "((?u:a))");
+        assert_eq!(canon("a{1}?"), "(?u:a)");
+    }
+
+    #[test]
+    fn literal_chars() {
+        let chars: Vec<char> = e("a[b-d]e").literal_chars().collect();
+        assert_eq!(chars, vec!['a', 'b', 'd', 'e']);
+        let chars: Vec<char> = e("(x|[yz0])+w").literal_chars().collect();
+        assert_eq!(chars, vec!['x', '0', 'y', 'z', 'w']);
+        assert_eq!(e(r"^.\b$").literal_chars().count(), 0);
+    }
+
+    #[test]
+    fn capture_names() {
+        let expr = e(r"(?P<a>x(y)(?:z))|((?P<b>w)|(?P<c>v))");
+        assert_eq!(expr.capture_count(), 5);
+        assert_eq!(expr.capture_names(), vec![
+            Some("a"), None, None, Some("b"), Some("c"),
+        ]);
+
+        assert_eq!(e("(?:a)b").capture_count(), 0);
+        assert!(e("(?:a)b").capture_names().is_empty());
+    }
+
+    fn named(i: usize, name: Option<&str>, e: Expr) -> Expr {
+        Expr::Group {
+            e: Box::new(e),
+            i: Some(i),
+            name: name.map(|n| n.to_owned()),
+        }
+    }
+
+    #[test]
+    fn duplicate_capture_name() {
+        let expr = Expr::Concat(vec![
+            named(1, Some("a"), e("x")),
+            named(2, Some("b"), e("y")),
+            named(3, Some("b"), named(4, Some("a"), e("z"))),
+        ]);
+        assert_eq!(expr.duplicate_capture_name(), Some(("b", 2, 3)));
+    }
+
+    #[test]
+    fn duplicate_capture_name_unique() {
+        assert_eq!(e(r"(?P<a>x)(?P<b>y)(z)").duplicate_capture_name(), None);
+        assert_eq!(e(r"x").duplicate_capture_name(), None);
+    }
+
+    #[test]
+    fn duplicate_capture_name_mixed() {
+        // Unnamed groups never clash, even with each other.
+        let expr = Expr::Alternate(vec![
+            named(1, None, e("x")),
+            named(2, Some("a"), named(3, None, e("y"))),
+            named(4, None, e("z")),
+            named(5, Some("a"), e("w")),
+        ]);
+        assert_eq!(expr.duplicate_capture_name(), Some(("a", 2, 5)));
+    }
+
+    #[test]
+    fn class_from_chars() {
+        let mut cls = CharClass::from_chars(&['c', 'a', 'b', 'a']);
+        assert_eq!(cls, class(&[('a', 'c')]));
+        assert_eq!(cls.to_string(), "(?u:[a-c])");
+
+        cls.push_range('z', 'x');
+        cls.push_range('d', 'd');
+        assert_eq!(cls, class(&[('a', 'd'), ('x', 'z')]));
+        assert_eq!(cls.to_string(), "(?u:[a-dx-z])");
+        assert_eq!(Expr::parse(&cls.to_string()).unwrap(), Expr::Class(cls));
+    }
+
+    #[test]
+    fn class_to_ranges() {
+        fn ranges(re: &str) -> Vec<(char, char)> {
+            match e(re) {
+                Expr::Class(cls) => cls.to_ranges(),
+                e => panic!("expected a class, got {:?}", e),
+            }
+        }
+        assert_eq!(ranges("[a-cx-z]"), vec![('a', 'c'), ('x', 'z')]);
+        assert_eq!(ranges("[a-z&&[p-t]]"), vec![('p', 't')]);
+        assert_eq!(ranges("[^a]"), vec![('\x00', '`'), ('b', '\u{10FFFF}')]);
+    }
+
     #[test]
     fn class_canon_no_change() {
         let cls = class(&[('a', 'c'), ('x', 'z')]);
@@ -2215,6 +3887,311 @@ mod tests {
         assert_eq!("(?-u:[-\\.-/])", expr.to_string());
     }
 
+    #[test]
+    fn parse_partial() {
+        let b = || ExprBuilder::new().stop_chars(&['/']);
+
+        let (expr, end) = b().parse_partial("abc/xyz").unwrap();
+        assert_eq!(expr, e("abc"));
+        assert_eq!(end, 3);
+
+        let (expr, end) = b().parse_partial("(a|b)+δ/i").unwrap();
+        assert_eq!(expr, e("(a|b)+δ"));
+        assert_eq!(&"(a|b)+δ/i"[end..], "/i");
+
+        // An unopened `)` ends parsing even without stop characters.
+        let (expr, end) = ExprBuilder::new().parse_partial("a(b))c").unwrap();
+        assert_eq!(expr, e("a(b)"));
+        assert_eq!(end, 4);
+
+        let (expr, end) = b().parse_partial("abc").unwrap();
+        assert_eq!(expr, e("abc"));
+        assert_eq!(end, 3);
+
+        let (expr, end) = b().parse_partial("/abc").unwrap();
+        assert_eq!(expr, Expr::Empty);
+        assert_eq!(end, 0);
+
+        let (expr, end) = b().parse_partial(r"a[/]\/b/c").unwrap();
+        assert_eq!(expr, e("a[/]/b"));
+        assert_eq!(end, 7);
+        assert!(Expr::parse(r"\/").is_err());
+        let (expr, _) = ExprBuilder::new().stop_chars(&['n'])
+            .parse_partial(r"a\nb").unwrap();
+        assert_eq!(expr, e(r"a\nb"));
+
+        let err = b().parse_partial("(a/b)").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::UnclosedParen);
+        assert!(b().parse_partial("a|/").is_err());
+    }
+
+    #[test]
+    fn parse_collect_errors() {
+        let (expr, errs) = ExprBuilder::new().parse_collect_errors("a|b");
+        assert_eq!(expr, Some(e("a|b")));
+        assert!(errs.is_empty());
+
+        let pat = r"a{2,1}|(b)|[z-a]|\pX|(c)";
+        let (expr, errs) = ExprBuilder::new().parse_collect_errors(pat);
+        assert_eq!(expr, None);
+        let kinds: Vec<_> = errs.iter().map(|e| e.kind().clone()).collect();
+        assert_eq!(kinds, vec![
+            ErrorKind::InvalidRepeatRange { min: 2, max: 1 },
+            ErrorKind::InvalidClassRange { start: 'z', end: 'a' },
+            ErrorKind::UnrecognizedUnicodeClass("X".to_owned()),
+        ]);
+        let positions: Vec<_> = errs.iter().map(|e| e.position()).collect();
+        assert_eq!(positions, vec![5, 15, 20]);
+    }
+
+    #[test]
+    fn parse_collect_errors_in_one_alternative() {
+        let positions = |pat: &str| -> Vec<usize> {
+            let (expr, errs) = ExprBuilder::new().parse_collect_errors(pat);
+            assert_eq!(expr, None);
+            errs.iter().map(|e| e.position()).collect()
+        };
+        assert_eq!(positions(r"(a**)(b**)"), vec![3, 8]);
+        assert_eq!(positions(r"[z-a][y-b]"), vec![4, 9]);
+        assert_eq!(positions(r"((a**)c[z-a])|(b**)"), vec![4, 11, 17]);
+        assert_eq!(positions(r"[a[z-a]](?P<n>a)(?P<n>b)"), vec![6, 22]);
+        // Nothing after an error outside of a group is checked.
+        assert_eq!(positions(r"a**(b**)|c**"), vec![2, 11]);
+
+        // Later errors are shown in the original pattern.
+        let (_, errs) = ExprBuilder::new().parse_collect_errors("(a**)(b**)");
+        assert_eq!(errs[1].surround, "*)(b**)");
+    }
+
+    #[test]
+    fn parse_collect_errors_unrecoverable() {
+        let pat = r"(?P<n>a)|(?P<n>b)";
+        let (expr, errs) = ExprBuilder::new().parse_collect_errors(pat);
+        assert_eq!(expr, None);
+        assert_eq!(errs, vec![Expr::parse(pat).unwrap_err()]);
+
+        let (expr, errs) = ExprBuilder::new().parse_collect_errors("(a|b");
+        assert_eq!(expr, None);
+        assert_eq!(errs.len(), 1);
+    }
+
+    #[test]
+    fn error_kind_suggest() {
+        let err = Expr::parse(r"\p{Greeek}").unwrap_err();
+        assert_eq!(err.kind().suggest(), Some("Greek".to_owned()));
+        let kind = ErrorKind::UnrecognizedUnicodeClass("Greek".to_owned());
+        assert_eq!(kind.suggest(), Some("Greek".to_owned()));
+        let kind = ErrorKind::UnrecognizedUnicodeClass("Zzz".to_owned());
+        assert_eq!(kind.suggest(), None);
+        assert_eq!(ErrorKind::EmptyGroup.suggest(), None);
+    }
+
+    #[test]
+    fn error_kind_codes_are_distinct() {
+        use std::collections::HashSet;
+        use ErrorKind::*;
+
+        // This fails to compile when a kind is added, as a reminder to list
+        // it below.
+        fn listed(kind: &ErrorKind) {
+            match *kind {
+                DoubleFlagNegation
+                | DuplicateCaptureName(_)
+                | EmptyAlternate
+                | EmptyCaptureName
+                | EmptyFlagNegation
+                | EmptyGroup
+                | InvalidBase10(_)
+                | InvalidBase16(_)
+                | InvalidCaptureName(_)
+                | InvalidClassRange{..}
+                | InvalidClassEscape(_)
+                | InvalidRepeatRange{..}
+                | InvalidScalarValue(_)
+                | MissingBase10
+                | RepeaterExpectsExpr
+                | RepeaterUnexpectedExpr(_)
+                | UnclosedCaptureName(_)
+                | UnclosedHex
+                | UnclosedParen
+                | UnclosedRepeat
+                | UnclosedUnicodeName
+                | UnexpectedClassEof
+                | UnexpectedEscapeEof
+                | UnexpectedFlagEof
+                | UnexpectedTwoDigitHexEof
+                | UnopenedParen
+                | UnrecognizedEscape(_)
+                | UnrecognizedFlag(_)
+                | UnrecognizedUnicodeClass(_)
+                | StackExhausted
+                | FlagNotAllowed(_)
+                | UnicodeNotAllowed
+                | InvalidUtf8
+                | EmptyClass
+                | UnsupportedClassChar(_)
+                | UnsupportedLookAround(_)
+                | ClassNestLimitExceeded(_)
+                | __Nonexhaustive => {}
+            }
+        }
+
+        let kinds = vec![
+            DoubleFlagNegation,
+            DuplicateCaptureName("a".to_owned()),
+            EmptyAlternate,
+            EmptyCaptureName,
+            EmptyFlagNegation,
+            EmptyGroup,
+            InvalidBase10("a".to_owned()),
+            InvalidBase16("g".to_owned()),
+            InvalidCaptureName("0".to_owned()),
+            InvalidClassRange { start: 'b', end: 'a' },
+            InvalidClassEscape(Expr::StartText),
+            InvalidRepeatRange { min: 2, max: 1 },
+            InvalidScalarValue(0xD800),
+            MissingBase10,
+            RepeaterExpectsExpr,
+            RepeaterUnexpectedExpr(Expr::StartText),
+            UnclosedCaptureName("a".to_owned()),
+            UnclosedHex,
+            UnclosedParen,
+            UnclosedRepeat,
+            UnclosedUnicodeName,
+            UnexpectedClassEof,
+            UnexpectedEscapeEof,
+            UnexpectedFlagEof,
+            UnexpectedTwoDigitHexEof,
+            UnopenedParen,
+            UnrecognizedEscape('q'),
+            UnrecognizedFlag('q'),
+            UnrecognizedUnicodeClass("Q".to_owned()),
+            StackExhausted,
+            FlagNotAllowed('u'),
+            UnicodeNotAllowed,
+            InvalidUtf8,
+            EmptyClass,
+            UnsupportedClassChar('-'),
+            UnsupportedLookAround(LookAroundKind::Ahead),
+            ClassNestLimitExceeded(200),
+        ];
+        let mut codes = HashSet::new();
+        for kind in &kinds {
+            listed(kind);
+            assert!(codes.insert(kind.code()), "duplicate code: {:?}", kind);
+        }
+        assert_eq!(codes.len(), 37);
+        assert_eq!(EmptyGroup.code(), 6);
+        assert_eq!(Expr::parse("()").unwrap_err().code(), 6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        use serde_json;
+
+        let exprs = vec![
+            e(r"(?i)(foo|bar)+[a-z]{2,4}"),
+            e(r"^(?P<name>\w+?)\b.*$"),
+            ExprBuilder::new().allow_bytes(true)
+                .parse(r"(?-u)[\x00-\x7F]\xFF?").unwrap(),
+        ];
+        for expr in exprs {
+            let json = serde_json::to_string(&expr).unwrap();
+            let got: Expr = serde_json::from_str(&json).unwrap();
+            assert_eq!(got, expr);
+        }
+    }
+
+    #[test]
+    fn literal_prefix() {
+        assert_eq!(e(r"abc[0-9]").literal_prefix(), "abc");
+        // Parsing removes the group and merges the literals...
+        assert_eq!(e(r"(?:x)abc").literal_prefix(), "xabc");
+        // ...but a group that remains ends the prefix.
+        let expr = Expr::Concat(vec![
+            Expr::Group { e: Box::new(e("x")), i: None, name: None },
+            e("abc"),
+        ]);
+        assert_eq!(expr.literal_prefix(), "");
+        assert_eq!(e(r"(x)abc").literal_prefix(), "");
+        assert_eq!(e(r"\x61bc").literal_prefix(), "abc");
+        assert_eq!(e(r"\Aab(?i)c").literal_prefix(), "ab");
+        assert_eq!(e(r"ab+").literal_prefix(), "a");
+        assert_eq!(e(r"☃").literal_prefix(), "☃");
+        assert_eq!(e(r"a|b").literal_prefix(), "");
+        assert_eq!(e(r"(?i)a").literal_prefix(), "");
+        assert_eq!(e(r"").literal_prefix(), "");
+    }
+
+    #[test]
+    fn depth() {
+        assert_eq!(e(r"a").depth(), 0);
+        assert_eq!(e(r"[a-z]").depth(), 0);
+        assert_eq!(e(r"ab*").depth(), 2);
+        assert_eq!(e(r"a(b|c)*").depth(), 4);
+        assert_eq!(e(r"((a))|b").depth(), 3);
+        assert_eq!(e(r"(?:a(?:b(?:c|d)))+").depth(), 7);
+
+        let mut deep = e(r"a");
+        for _ in 0..1000 {
+            deep = Expr::Group { e: Box::new(deep), i: None, name: None };
+        }
+        assert_eq!(deep.depth(), 1000);
+    }
+
+    #[test]
+    fn hash_consistent_with_eq() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        set.insert(e(r"(?i)(foo|bar)+[a-z]{2,4}"));
+        set.insert(e(r"(?i)(foo|bar)+[a-z]{2,4}"));
+        assert_eq!(set.len(), 1);
+        set.insert(e(r"(?i)(foo|bar)+[a-z]{2,5}"));
+        set.insert(e(r"(?i)(foo|bar)+?[a-z]{2,4}"));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&e(r"(?i)(foo|bar)+[a-z]{2,5}")));
+    }
+
+    #[test]
+    fn error_snippet() {
+        let pat = r"ab(c";
+        let err = Expr::parse(pat).unwrap_err();
+        assert_eq!(err.snippet(pat), format!("{}\nab(c\n  ^", err));
+
+        let pat = r"☃\xZZ";
+        let err = Expr::parse(pat).unwrap_err();
+        assert_eq!(err.snippet(pat), format!("{}\n☃\\xZZ\n     ^", err));
+    }
+
+    #[test]
+    fn error_snippet_multi_line() {
+        let pat = "(?x)\n  a # first\n  b{2,1}\n  c";
+        let err = Expr::parse(pat).unwrap_err();
+        assert_eq!(err.snippet(pat),
+                   format!("{}\n  b{{2,1}}\n       ^", err));
+    }
+
+    #[test]
+    fn roundtrip_class_sorted() {
+        // Classes are canonicalized when parsed, so the order in which items
+        // are written never affects the printed form.
+        assert_eq!("(?u:[a-c])", e("[cba]").to_string());
+        assert_eq!("(?u:[0-9a-c])", e("[c0-9b[a]]").to_string());
+        assert_eq!("(?-u:[a-cx-x])", e("(?-u)[xb-ca]").to_string());
+    }
+
+    #[test]
+    fn class_normalized_when_parsed() {
+        // There's no separate class syntax tree to normalize: every class is
+        // a canonical list of ranges as soon as it is parsed.
+        assert_eq!(e("[bac]"), e("[abc]"));
+        assert_eq!(e("[a-ce-gd]"), e("[a-g]"));
+        assert_eq!(e("[[b][a]c]"), e("[a-c]"));
+        assert_eq!(e("[a-cb-d]"), e("[a-d]"));
+    }
+
     fn assert_intersection(cls1: CharClass, cls2: CharClass, expected: CharClass) {
         // intersection operation should be commutative
         assert_eq!(cls1.intersection(&cls2), expected);
@@ -9,14 +9,16 @@
 // except according to those terms.
 
 use std::cmp::{max, min};
+use std::mem;
 use std::u8;
+use std::usize;
 
 use unicode::regex::UNICODE_CLASSES;
 
 use {
     Expr, Repeater, CharClass, ClassRange,
     CaptureIndex, CaptureName,
-    Error, ErrorKind, Result,
+    Error, ErrorKind, LookAroundKind, Result,
 };
 
 /// Parser state.
@@ -33,6 +35,9 @@ pub struct Parser {
     caps: usize,
     names: Vec<String>, // to check for duplicates
     flags: Flags,
+    // When set, only a prefix of the input is parsed. Parsing stops at the
+    // first of these characters, or at a `)` that doesn't close a group.
+    stop_chars: Option<Vec<char>>,
 }
 
 /// Flag state used in the parser.
@@ -53,6 +58,12 @@ pub struct Flags {
     /// Not actually a flag, but when disabled, every regex that may not match
     /// UTF-8 exclusively will cause the parser to return an error.
     pub allow_bytes: bool,
+    /// Not actually a flag, but when enabled, whitespace around the name in
+    /// `\p{...}` is ignored even when `x` is disabled.
+    pub lenient_property_names: bool,
+    /// Not actually a flag, but the maximum depth to which character
+    /// classes may be nested. e.g., `[[a]]` has a depth of `2`.
+    pub class_nest_limit: usize,
 }
 
 impl Default for Flags {
@@ -65,6 +76,8 @@ impl Default for Flags {
             ignore_space: false,
             unicode: true,
             allow_bytes: false,
+            lenient_property_names: false,
+            class_nest_limit: usize::MAX,
         }
     }
 }
@@ -105,24 +118,62 @@ enum Bracket {
 // Primary expression parsing routines.
 impl Parser {
     pub fn parse(s: &str, flags: Flags) -> Result<Expr> {
+        Parser::new(flags).parse_reusing(s, flags)
+    }
+
+    pub fn new(flags: Flags) -> Parser {
         Parser {
-            chars: s.chars().collect(),
+            chars: vec![],
             chari: 0,
             stack: vec![],
             caps: 0,
             names: vec![],
             flags: flags,
-        }.parse_expr()
+            stop_chars: None,
+        }
+    }
+
+    // Parses the longest prefix of `s` that can be parsed without running
+    // into a character in `stop_chars` or an unopened `)`.
+    //
+    // Returns the expression along with the byte offset in `s` at which
+    // parsing stopped.
+    pub fn parse_partial(
+        s: &str,
+        flags: Flags,
+        stop_chars: &[char],
+    ) -> Result<(Expr, usize)> {
+        let mut p = Parser::new(flags);
+        p.chars.extend(s.chars());
+        p.stop_chars = Some(stop_chars.to_vec());
+        let expr = try!(p.parse_expr());
+        let end = p.chars[..p.chari].iter().map(|c| c.len_utf8()).sum();
+        Ok((expr, end))
+    }
+
+    // Parses `s` with the given flags.
+    //
+    // All parser state is reset first, but the buffers allocated by previous
+    // calls are reused.
+    pub fn parse_reusing(&mut self, s: &str, flags: Flags) -> Result<Expr> {
+        self.chars.clear();
+        self.chars.extend(s.chars());
+        self.chari = 0;
+        self.stack.clear();
+        self.caps = 0;
+        self.names.clear();
+        self.flags = flags;
+        self.parse_expr()
     }
 
     // Top-level expression parser.
     //
     // Starts at the beginning of the input and consumes until either the end
     // of input or an error.
-    fn parse_expr(mut self) -> Result<Expr> {
+    fn parse_expr(&mut self) -> Result<Expr> {
         loop {
             self.ignore_space();
-            if self.eof() {
+            if self.eof() || self.at_stop() {
                 break;
             }
             let build_expr = match self.cur() {
@@ -187,6 +238,28 @@ impl Parser {
         self.finish_concat()
     }
 
+    // Returns true if a partial parse should stop at the current character.
+    //
+    // Stop characters are only seen here outside of classes and escapes,
+    // which treat them as literals.
+    fn at_stop(&self) -> bool {
+        if self.stop_chars.is_none() {
+            return false;
+        }
+        match self.cur() {
+            c if self.is_stop_char(c) => true,
+            ')' => !self.stack.iter().any(|b| match *b {
+                Build::LeftParen{..} => true,
+                Build::Expr(_) => false,
+            }),
+            _ => false,
+        }
+    }
+
+    fn is_stop_char(&self, c: char) -> bool {
+        self.stop_chars.as_ref().map_or(false, |cs| cs.contains(&c))
+    }
+
     // Parses an escape sequence, e.g., \Ax
     //
     // Start: `\`
@@ -237,6 +310,11 @@ impl Parser {
                 self.bump();
                 Ok(Build::Expr(Expr::Class(self.parse_perl_class(c))))
             }
+            // e.g., \/ when `/` ends a partial parse
+            c if self.is_stop_char(c) => {
+                self.bump();
+                Ok(try!(self.lit(c)))
+            }
             c => Err(self.err(ErrorKind::UnrecognizedEscape(c))),
         }
     }
@@ -262,6 +340,10 @@ impl Parser {
             }
             self.names.push(n.clone());
             name = Some(n);
+        } else if let Some(kind) = self.look_around_kind() {
+            // e.g., (?<=a)
+            let kind = ErrorKind::UnsupportedLookAround(kind);
+            return Err(self.errat(chari, kind));
         } else if self.bump_if("?") {
             // This can never be capturing. It's either setting flags for
             // the current group, or it's opening a non-capturing group or
@@ -351,6 +433,24 @@ impl Parser {
         }
     }
 
+    // Returns the kind of look-around that the group starting at the
+    // current position would open, if any. Nothing is consumed.
+    //
+    // Start: `?` in `(?<=a)`
+    fn look_around_kind(&self) -> Option<LookAroundKind> {
+        if self.peek_is("?=") {
+            Some(LookAroundKind::Ahead)
+        } else if self.peek_is("?!") {
+            Some(LookAroundKind::NegAhead)
+        } else if self.peek_is("?<=") {
+            Some(LookAroundKind::Behind)
+        } else if self.peek_is("?<!") {
+            Some(LookAroundKind::NegBehind)
+        } else {
+            None
+        }
+    }
+
     // Parses a group name, e.g., `foo` in `(?P<foo>abc)`.
     //
     // Start: `f`
@@ -585,6 +685,7 @@ impl Parser {
     // End:   `+`
     fn parse_class_as_chars(&mut self) -> Result<CharClass> {
         let mut bracket_stack = vec![];
+        let mut depth = 1;
         bracket_stack.extend(self.parse_open_bracket());
         loop {
             self.ignore_space();
@@ -599,12 +700,20 @@ impl Parser {
                         bracket_stack.push(Bracket::Set(class));
                     } else {
                         // nested set, e.g. `[c-d]` in `[a-b[c-d]]`
+                        let limit = self.flags.class_nest_limit;
+                        if depth >= limit {
+                            // e.g., [[[a]]] with a limit of 2
+                            return Err(self.err(
+                                ErrorKind::ClassNestLimitExceeded(limit)));
+                        }
+                        depth += 1;
                         bracket_stack.extend(self.parse_open_bracket());
                     }
                 }
                 ']' => {
                     self.bump();
                     let class = try!(self.close_bracket(&mut bracket_stack));
+                    depth -= 1;
                     if bracket_stack.is_empty() {
                         // That was the outermost class, so stop now
                         return Ok(class);
@@ -822,9 +931,9 @@ impl Parser {
         self.ignore_space();
         let name =
             if self.bump_if('{') {
-                self.ignore_space();
+                self.ignore_property_space();
                 let n = self.bump_get(is_ascii_word).unwrap_or("".into());
-                self.ignore_space();
+                self.ignore_property_space();
                 if n.is_empty() || !self.bump_if('}') {
                     // e.g., \p{Greek
                     return Err(self.err(ErrorKind::UnclosedUnicodeName));
@@ -855,22 +964,7 @@ impl Parser {
     //
     // No parser state is changed.
     fn parse_perl_class(&mut self, name: char) -> CharClass {
-        use unicode::regex::{PERLD, PERLS, PERLW};
-        let (cls, negate) = match (self.flags.unicode, name) {
-            (true, 'd') => (raw_class_to_expr(PERLD), false),
-            (true, 'D') => (raw_class_to_expr(PERLD), true),
-            (true, 's') => (raw_class_to_expr(PERLS), false),
-            (true, 'S') => (raw_class_to_expr(PERLS), true),
-            (true, 'w') => (raw_class_to_expr(PERLW), false),
-            (true, 'W') => (raw_class_to_expr(PERLW), true),
-            (false, 'd') => (ascii_class("digit").unwrap(), false),
-            (false, 'D') => (ascii_class("digit").unwrap(), true),
-            (false, 's') => (ascii_class("space").unwrap(), false),
-            (false, 'S') => (ascii_class("space").unwrap(), true),
-            (false, 'w') => (ascii_class("word").unwrap(), false),
-            (false, 'W') => (ascii_class("word").unwrap(), true),
-            _ => unreachable!(),
-        };
+        let (cls, negate) = perl_class(name, self.flags.unicode).unwrap();
         self.class_transform(negate, cls)
     }
 
@@ -915,6 +1009,17 @@ impl Parser {
         }
     }
 
+    // Like `ignore_space`, but also skips whitespace when lenient property
+    // names are enabled. This is only used inside the braces of `\p{...}`.
+    fn ignore_property_space(&mut self) {
+        if self.flags.lenient_property_names {
+            while !self.eof() && self.cur().is_whitespace() {
+                self.bump();
+            }
+        }
+        self.ignore_space();
+    }
+
     fn bump(&mut self) -> char {
         let c = self.cur();
         self.chari = checkadd(self.chari, self.chars().next_count());
@@ -1393,12 +1498,159 @@ fn unicode_class(name: &str) -> Option<CharClass> {
     })
 }
 
+/// Returns the name of the Unicode class that is closest to `name`, if any
+/// is close enough to be a plausible correction.
+///
+/// Closeness is measured by Levenshtein distance. Names within a distance of
+/// `2` are considered, but short names allow less (a third of their length,
+/// rounded down, and at least `1`) so that every short name doesn't suggest
+/// some unrelated one or two letter category. Ties go to the name that sorts
+/// first.
+pub fn suggest_unicode_class(name: &str) -> Option<&'static str> {
+    let limit = min(2, max(1, name.chars().count() / 3));
+    let mut best = None;
+    for &(candidate, _) in UNICODE_CLASSES {
+        let dist = levenshtein(name, candidate);
+        if dist > limit {
+            continue;
+        }
+        match best {
+            Some((_, best_dist)) if best_dist <= dist => {}
+            _ => best = Some((candidate, dist)),
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+/// Returns the number of single character insertions, deletions and
+/// substitutions needed to turn `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..b.len() + 1).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let subst = prev[j] + if ca == cb { 0 } else { 1 };
+            cur[j + 1] = min(subst, min(prev[j + 1], cur[j]) + 1);
+        }
+        mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
 fn ascii_class(name: &str) -> Option<CharClass> {
     ASCII_CLASSES.binary_search_by(|&(s, _)| s.cmp(name)).ok().map(|i| {
         raw_class_to_expr(ASCII_CLASSES[i].1)
     })
 }
 
+/// Returns the un-negated class for the Perl class `\name` along with
+/// whether it must be negated, or `None` if `name` isn't one of d, s, w, D,
+/// S or W.
+pub fn perl_class(name: char, unicode: bool) -> Option<(CharClass, bool)> {
+    use unicode::regex::{PERLD, PERLS, PERLW};
+    Some(match (unicode, name) {
+        (true, 'd') => (raw_class_to_expr(PERLD), false),
+        (true, 'D') => (raw_class_to_expr(PERLD), true),
+        (true, 's') => (raw_class_to_expr(PERLS), false),
+        (true, 'S') => (raw_class_to_expr(PERLS), true),
+        (true, 'w') => (raw_class_to_expr(PERLW), false),
+        (true, 'W') => (raw_class_to_expr(PERLW), true),
+        (false, 'd') => (raw_class_to_expr(DIGIT), false),
+        (false, 'D') => (raw_class_to_expr(DIGIT), true),
+        (false, 's') => (raw_class_to_expr(SPACE), false),
+        (false, 'S') => (raw_class_to_expr(SPACE), true),
+        (false, 'w') => (raw_class_to_expr(WORD), false),
+        (false, 'W') => (raw_class_to_expr(WORD), true),
+        _ => return None,
+    })
+}
+
+/// Splits a pattern into the character ranges of its top level alternatives.
+///
+/// This only understands enough syntax to skip over escapes, groups and
+/// character classes, so that it also works on patterns that don't parse.
+pub fn split_alternates(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    let (mut start, mut depth, mut class_depth) = (0, 0usize, 0usize);
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => {
+                class_depth += 1;
+                // A `]` right after the opening bracket is a literal.
+                if chars.get(i + 1) == Some(&'^') {
+                    i += 1;
+                }
+                if chars.get(i + 1) == Some(&']') {
+                    i += 1;
+                }
+            }
+            ']' if class_depth > 0 => class_depth -= 1,
+            _ if class_depth > 0 => {}
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => {
+                ranges.push((start, i));
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    ranges.push((start, chars.len()));
+    ranges
+}
+
+/// Returns the character range, delimiters included, of the innermost group
+/// or character class around the character at `pos`.
+///
+/// Like `split_alternates`, this only understands escapes, groups and
+/// character classes. Groups and classes that aren't closed are ignored.
+pub fn enclosing_group(chars: &[char], pos: usize) -> Option<(usize, usize)> {
+    // The start of every group or class that is still open, and whether it
+    // is a class.
+    let mut open: Vec<(usize, bool)> = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let in_class = open.last().map_or(false, |&(_, class)| class);
+        let closed = match chars[i] {
+            '\\' => {
+                i += 1;
+                None
+            }
+            '[' => {
+                open.push((i, true));
+                // A `]` right after the opening bracket is a literal.
+                if chars.get(i + 1) == Some(&'^') {
+                    i += 1;
+                }
+                if chars.get(i + 1) == Some(&']') {
+                    i += 1;
+                }
+                None
+            }
+            ']' if in_class => open.pop(),
+            _ if in_class => None,
+            '(' => {
+                open.push((i, false));
+                None
+            }
+            ')' => open.pop(),
+            _ => None,
+        };
+        if let Some((start, _)) = closed {
+            if start <= pos && pos <= i {
+                return Some((start, i + 1));
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
 fn raw_class_to_expr(raw: &[(char, char)]) -> CharClass {
     let range = |&(s, e)| ClassRange { start: s, end: e };
     CharClass::new(raw.iter().map(range).collect())
@@ -1461,10 +1713,13 @@ mod tests {
     use {
         CharClass, ClassRange, ByteClass, ByteRange,
         Expr, Repeater,
-        ErrorKind,
+        ErrorKind, LookAroundKind,
     };
     use unicode::regex::{PERLD, PERLS, PERLW};
-    use super::{LOWER, UPPER, WORD, Flags, Parser, ascii_class};
+    use super::{
+        LOWER, UPPER, WORD, Flags, Parser, ascii_class, levenshtein,
+        enclosing_group, split_alternates, suggest_unicode_class,
+    };
 
     static YI: &'static [(char, char)] = &[
         ('\u{a000}', '\u{a48c}'), ('\u{a490}', '\u{a4c6}'),
@@ -2046,6 +2301,20 @@ mod tests {
         assert_eq!(p(r"\p{Yi}"), Expr::Class(class(YI)));
     }
 
+    #[test]
+    fn escape_unicode_name_lenient() {
+        let flags = Flags {
+            lenient_property_names: true,
+            .. Flags::default()
+        };
+        assert_eq!(pf(r"\p{ Yi }", flags), Expr::Class(class(YI)));
+        assert_eq!(pf("\\P{\tYi\n}", flags),
+                   Expr::Class(class(YI).negate()));
+        assert_eq!(pf(r"[\p{  Yi}]", flags), Expr::Class(class(YI)));
+        assert_eq!(pf("(?x)\\p{ Yi # comment\n}", flags),
+                   Expr::Class(class(YI)));
+    }
+
     #[test]
     fn escape_unicode_letter() {
         assert_eq!(p(r"\pZ"), Expr::Class(class(&[
@@ -2486,6 +2755,16 @@ mod tests {
         assert_eq!(p(&s), Expr::Class(class(&[('a', 'a')])));
     }
 
+    #[test]
+    fn class_nested_class_nest_limit() {
+        let flags = Flags { class_nest_limit: 3, .. Flags::default() };
+        assert_eq!(Parser::parse("[[[a]]]", flags).unwrap(),
+                   Expr::Class(class(&[('a', 'a')])));
+        let err = Parser::parse("[[a][[[b]]]]", flags).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ClassNestLimitExceeded(3));
+        assert_eq!(err.position(), 6);
+    }
+
     #[test]
     fn class_intersection_ranges() {
         assert_eq!(p(r"[abc&&b-c]"), Expr::Class(class(&[('b', 'c')])));
@@ -2603,6 +2882,55 @@ mod tests {
                    Expr::ClassBytes(asciiw_bytes().negate()));
     }
 
+    fn alternates(s: &str) -> Vec<String> {
+        let chars: Vec<char> = s.chars().collect();
+        split_alternates(&chars).into_iter().map(|(start, end)| {
+            chars[start..end].iter().cloned().collect()
+        }).collect()
+    }
+
+    #[test]
+    fn split_alternates_top_level() {
+        assert_eq!(alternates(r"a|b(c|d)|"), vec!["a", "b(c|d)", ""]);
+        assert_eq!(alternates(r"[|]|\||[]|]|[^]|]"),
+                   vec!["[|]", r"\|", "[]|]", "[^]|]"]);
+        assert_eq!(alternates(r"a(|b"), vec!["a(|b"]);
+        assert_eq!(alternates(r"a)|b"), vec!["a)", "b"]);
+        assert_eq!(alternates(r"δ|☃"), vec!["δ", "☃"]);
+    }
+
+    #[test]
+    fn enclosing_group_innermost() {
+        let chars: Vec<char> = r"a(b[c-d]\)(e))[]]".chars().collect();
+        assert_eq!(enclosing_group(&chars, 0), None);
+        assert_eq!(enclosing_group(&chars, 2), Some((1, 14)));
+        assert_eq!(enclosing_group(&chars, 5), Some((3, 8)));
+        assert_eq!(enclosing_group(&chars, 9), Some((1, 14)));
+        assert_eq!(enclosing_group(&chars, 11), Some((10, 13)));
+        assert_eq!(enclosing_group(&chars, 16), Some((14, 17)));
+
+        let chars: Vec<char> = r"(a|b".chars().collect();
+        assert_eq!(enclosing_group(&chars, 1), None);
+    }
+
+    #[test]
+    fn class_intersection_chain() {
+        // Intersection is associative, so every grouping of a chain must
+        // produce the same class, and printing it must round-trip.
+        let want = Expr::Class(class(&[('d', 'f')]));
+        for re in &[
+            r"[a-z&&b-y&&c-x&&d-f]",
+            r"[[a-z&&b-y]&&[c-x&&d-f]]",
+            r"[[[a-z&&b-y]&&c-x]&&d-f]",
+            r"[a-z&&[b-y&&[c-x&&d-f]]]",
+            r"[d-f&&c-x&&b-y&&a-z]",
+        ] {
+            let got = p(re);
+            assert_eq!(got, want);
+            assert_eq!(Expr::parse(&got.to_string()).unwrap(), want);
+        }
+    }
+
     #[test]
     fn class_intersection_caret() {
         // In `[a^]`, `^` does not need to be escaped, so it makes sense that
@@ -2752,6 +3080,15 @@ mod tests {
         assert_eq!(p("(?x) "), Expr::Empty);
     }
 
+    #[test]
+    fn ignore_space_comment_only() {
+        assert_eq!(p("(?x) # just a note\n"), Expr::Empty);
+        assert_eq!(p("(?x)# no trailing new line"), Expr::Empty);
+        assert_eq!(p("(?x)\n  # one\n\t# two\n\n"), Expr::Empty);
+        let flags = Flags { ignore_space: true, .. Flags::default() };
+        assert_eq!(pf("# only a comment\n", flags), Expr::Empty);
+    }
+
     #[test]
     fn ignore_space_literal() {
         assert_eq!(p("(?x) a b c"), Expr::Concat(vec![
@@ -3097,6 +3434,18 @@ mod tests {
         test_err!("(?z:a)", 2, ErrorKind::UnrecognizedFlag('z'));
     }
 
+    #[test]
+    fn error_group_look_around() {
+        test_err!("a(?=x)", 1,
+                  ErrorKind::UnsupportedLookAround(LookAroundKind::Ahead));
+        test_err!("(?!x)", 0,
+                  ErrorKind::UnsupportedLookAround(LookAroundKind::NegAhead));
+        test_err!("(?<=x)", 0,
+                  ErrorKind::UnsupportedLookAround(LookAroundKind::Behind));
+        test_err!("(?<!x)", 0,
+                  ErrorKind::UnsupportedLookAround(LookAroundKind::NegBehind));
+    }
+
     #[test]
     fn error_group_opts_unexpected_eof() {
         test_err!("(?i", 3, ErrorKind::UnexpectedFlagEof);
@@ -3179,6 +3528,11 @@ mod tests {
         test_err!(r"\p{Greek", 8, ErrorKind::UnclosedUnicodeName);
     }
 
+    #[test]
+    fn error_unicode_spaces_strict() {
+        test_err!(r"\p{ Yi }", 3, ErrorKind::UnclosedUnicodeName);
+    }
+
     #[test]
     fn error_unicode_no_letter() {
         test_err!(r"\p", 2, ErrorKind::UnexpectedEscapeEof);
@@ -3195,6 +3549,25 @@ mod tests {
                   ErrorKind::UnrecognizedUnicodeClass("Yii".into()));
     }
 
+    #[test]
+    fn unicode_class_suggestions() {
+        assert_eq!(suggest_unicode_class("Greek"), Some("Greek"));
+        assert_eq!(suggest_unicode_class("Greeek"), Some("Greek"));
+        assert_eq!(suggest_unicode_class("Cyrilic"), Some("Cyrillic"));
+        assert_eq!(suggest_unicode_class("Lx"), Some("L"));
+        assert_eq!(suggest_unicode_class("Zzz"), None);
+        assert_eq!(suggest_unicode_class("Yii"), Some("Yi"));
+    }
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("δx", "xδ"), 2);
+    }
+
     #[test]
     fn error_class_eof_empty() {
         test_err!("[", 1, ErrorKind::UnexpectedClassEof);
@@ -3248,6 +3621,7 @@ mod tests {
         test_err!("[]", 2, ErrorKind::UnexpectedClassEof);
         test_err!("[^]", 3, ErrorKind::UnexpectedClassEof);
         test_err!(r"[^\d\D]", 7, ErrorKind::EmptyClass);
+        test_err!(r"[^\x00-\x{10FFFF}]", 18, ErrorKind::EmptyClass);
 
         let flags = Flags { allow_bytes: true, .. Flags::default() };
         test_err!(r"(?-u)[^\x00-\xFF]", 17, ErrorKind::EmptyClass, flags);
@@ -3264,6 +3638,8 @@ mod tests {
         test_err!(r"[a-a--\xFF]", 5, ErrorKind::UnsupportedClassChar('-'));
         test_err!(r"[a&&~~]", 5, ErrorKind::UnsupportedClassChar('~'));
         test_err!(r"[a&&--]", 5, ErrorKind::UnsupportedClassChar('-'));
+        test_err!(r"[a-z--m-n--x]", 5, ErrorKind::UnsupportedClassChar('-'));
+        test_err!(r"[a-z~~m-n~~x]", 5, ErrorKind::UnsupportedClassChar('~'));
     }
 
     #[test]
@@ -3283,6 +3659,8 @@ mod tests {
         // `]` after `&&` is not the same as in (`[]]`), because it's also not
         // allowed unescaped in `[a]]`.
         test_err!(r"[]&&]]", 5, ErrorKind::EmptyClass);
+        test_err!(r"[a&&b]", 6, ErrorKind::EmptyClass);
+        test_err!(r"[a-c&&x-z]", 10, ErrorKind::EmptyClass);
 
         let flags = Flags { allow_bytes: true, .. Flags::default() };
         test_err!(r"(?-u)[a&&\pZ]", 12, ErrorKind::UnicodeNotAllowed, flags);
@@ -0,0 +1,165 @@
+// Copyright 2014-2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use {Expr, Repeater, quote_byte, quote_char};
+
+/// A node in a railroad (syntax) diagram of a regular expression.
+///
+/// This is a projection of an `Expr` intended for rendering a human readable
+/// picture of a regular expression. It mirrors the primitives offered by most
+/// railroad diagram generators. Information that doesn't affect the shape of
+/// the diagram, such as greediness and capture groups, is dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RailroadNode {
+    /// A sequence of nodes that must be matched one after the other.
+    ///
+    /// The empty sequence corresponds to the empty regex.
+    Sequence(Vec<RailroadNode>),
+    /// A choice between nodes, where exactly one must be matched.
+    Choice(Vec<RailroadNode>),
+    /// A node that may be skipped.
+    Optional(Box<RailroadNode>),
+    /// A node that may be repeated one or more times.
+    ///
+    /// When the repetition is a counted repetition, the label contains the
+    /// original `{m,n}` operator. Counted repetitions with a minimum of `0`
+    /// are wrapped in an `Optional` node.
+    Repeat(Box<RailroadNode>, Option<String>),
+    /// A leaf that matches text or asserts something about the text.
+    Terminal(String),
+}
+
+impl RailroadNode {
+    /// Builds a diagram for the given expression.
+    pub fn from_expr(expr: &Expr) -> RailroadNode {
+        use self::RailroadNode::*;
+        match *expr {
+            Expr::Empty => Sequence(vec![]),
+            Expr::Literal { ref chars, casei } => {
+                let lit: String =
+                    chars.iter().map(|&c| quote_char(c)).collect();
+                Terminal(case_label(lit, casei))
+            }
+            Expr::LiteralBytes { ref bytes, casei } => {
+                let lit: String =
+                    bytes.iter().map(|&b| quote_byte(b)).collect();
+                Terminal(case_label(lit, casei))
+            }
+            Expr::AnyChar => Terminal("any character".to_owned()),
+            Expr::AnyCharNoNL => {
+                Terminal("any character except new line".to_owned())
+            }
+            Expr::AnyByte => Terminal("any byte".to_owned()),
+            Expr::AnyByteNoNL => {
+                Terminal("any byte except new line".to_owned())
+            }
+            Expr::Class(ref cls) => Terminal(cls.to_string()),
+            Expr::ClassBytes(ref cls) => Terminal(cls.to_string()),
+            Expr::StartLine => Terminal("start of line".to_owned()),
+            Expr::EndLine => Terminal("end of line".to_owned()),
+            Expr::StartText => Terminal("start of text".to_owned()),
+            Expr::EndText => Terminal("end of text".to_owned()),
+            Expr::WordBoundary => Terminal("word boundary".to_owned()),
+            Expr::NotWordBoundary => {
+                Terminal("not a word boundary".to_owned())
+            }
+            Expr::WordBoundaryAscii => {
+                Terminal("ASCII word boundary".to_owned())
+            }
+            Expr::NotWordBoundaryAscii => {
+                Terminal("not an ASCII word boundary".to_owned())
+            }
+            Expr::Group { ref e, .. } => RailroadNode::from_expr(e),
+            Expr::Repeat { ref e, r, .. } => {
+                let node = Box::new(RailroadNode::from_expr(e));
+                match r {
+                    Repeater::ZeroOrOne => Optional(node),
+                    Repeater::ZeroOrMore => {
+                        Optional(Box::new(Repeat(node, None)))
+                    }
+                    Repeater::OneOrMore => Repeat(node, None),
+                    Repeater::Range { min, .. } => {
+                        let rep = Repeat(node, Some(r.to_string()));
+                        if min == 0 {
+                            Optional(Box::new(rep))
+                        } else {
+                            rep
+                        }
+                    }
+                }
+            }
+            Expr::Concat(ref es) => {
+                Sequence(es.iter().map(RailroadNode::from_expr).collect())
+            }
+            Expr::Alternate(ref es) => {
+                Choice(es.iter().map(RailroadNode::from_expr).collect())
+            }
+        }
+    }
+}
+
+fn case_label(lit: String, casei: bool) -> String {
+    if casei {
+        format!("{} (case insensitive)", lit)
+    } else {
+        lit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Expr;
+    use super::RailroadNode;
+    use super::RailroadNode::*;
+
+    fn rr(re: &str) -> RailroadNode {
+        Expr::parse(re).unwrap().to_railroad()
+    }
+
+    fn t(s: &str) -> RailroadNode {
+        Terminal(s.to_owned())
+    }
+
+    #[test]
+    fn sequence_choice_repeat() {
+        assert_eq!(rr("a(b|c)*d"), Sequence(vec![
+            t("a"),
+            Optional(Box::new(Repeat(
+                Box::new(Choice(vec![t("b"), t("c")])),
+                None,
+            ))),
+            t("d"),
+        ]));
+    }
+
+    #[test]
+    fn repeaters() {
+        assert_eq!(rr("a?"), Optional(Box::new(t("a"))));
+        assert_eq!(rr("a+?"), Repeat(Box::new(t("a")), None));
+        assert_eq!(rr("a{2,5}"),
+                   Repeat(Box::new(t("a")), Some("{2, 5}".to_owned())));
+        assert_eq!(rr("a{0,}"), Optional(Box::new(
+            Repeat(Box::new(t("a")), Some("{0,}".to_owned())))));
+    }
+
+    #[test]
+    fn terminals() {
+        assert_eq!(rr("(?i)a.b"), Sequence(vec![
+            t("a (case insensitive)"),
+            t("any character except new line"),
+            t("b (case insensitive)"),
+        ]));
+        assert_eq!(rr(r"^\bx$"), Sequence(vec![
+            t("start of text"), t("word boundary"), t("x"), t("end of text"),
+        ]));
+        assert_eq!(rr("[a-c]"), t("(?u:[a-c])"));
+        assert_eq!(rr(r"\."), t(r"\."));
+    }
+}
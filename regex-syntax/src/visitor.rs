@@ -0,0 +1,589 @@
+// Copyright 2014-2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use Expr;
+
+/// A trait for walking an `Expr` with `visit`.
+///
+/// Every method has a default implementation that does nothing, so
+/// implementors only need to override the methods for the expressions they
+/// care about. Each method is given the expression being visited.
+///
+/// Groups, repetitions, concatenations and alternations are visited twice:
+/// once before any of their sub-expressions (`pre`) and once after all of
+/// them (`post`). This makes it possible to track nesting depth.
+pub trait Visitor {
+    /// Called for `Expr::Empty`.
+    fn visit_empty(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Literal` and `Expr::LiteralBytes`.
+    fn visit_literal(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::AnyChar`, `Expr::AnyCharNoNL`, `Expr::AnyByte` and
+    /// `Expr::AnyByteNoNL`.
+    fn visit_any(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Class` and `Expr::ClassBytes`.
+    fn visit_class(&mut self, _expr: &Expr) {}
+
+    /// Called for anchors and word boundary assertions.
+    fn visit_assertion(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Group` before its sub-expression is visited.
+    fn visit_group_pre(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Group` after its sub-expression is visited.
+    fn visit_group_post(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Repeat` before its sub-expression is visited.
+    fn visit_repeat_pre(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Repeat` after its sub-expression is visited.
+    fn visit_repeat_post(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Concat` before its sub-expressions are visited.
+    fn visit_concat_pre(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Concat` after its sub-expressions are visited.
+    fn visit_concat_post(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Alternate` before its branches are visited.
+    fn visit_alternate_pre(&mut self, _expr: &Expr) {}
+
+    /// Called for `Expr::Alternate` after its branches are visited.
+    fn visit_alternate_post(&mut self, _expr: &Expr) {}
+}
+
+/// Walks `expr` in depth first order, calling the corresponding methods on
+/// `visitor`.
+///
+/// Sub-expressions are visited in the order they appear in the expression.
+/// The walk uses a heap allocated stack instead of recursion, so deeply
+/// nested expressions can't overflow the call stack.
+pub fn visit<V: Visitor>(expr: &Expr, visitor: &mut V) {
+    enum Frame<'a> {
+        Pre(&'a Expr),
+        Post(&'a Expr),
+    }
+
+    let mut stack = vec![Frame::Pre(expr)];
+    while let Some(frame) = stack.pop() {
+        let expr = match frame {
+            Frame::Pre(expr) => expr,
+            Frame::Post(expr) => {
+                match *expr {
+                    Expr::Group { .. } => visitor.visit_group_post(expr),
+                    Expr::Repeat { .. } => visitor.visit_repeat_post(expr),
+                    Expr::Concat(_) => visitor.visit_concat_post(expr),
+                    Expr::Alternate(_) => visitor.visit_alternate_post(expr),
+                    _ => unreachable!(),
+                }
+                continue;
+            }
+        };
+        match *expr {
+            Expr::Empty => visitor.visit_empty(expr),
+            Expr::Literal { .. } | Expr::LiteralBytes { .. } => {
+                visitor.visit_literal(expr)
+            }
+            Expr::AnyChar | Expr::AnyCharNoNL
+            | Expr::AnyByte | Expr::AnyByteNoNL => visitor.visit_any(expr),
+            Expr::Class(_) | Expr::ClassBytes(_) => visitor.visit_class(expr),
+            Expr::StartLine | Expr::EndLine
+            | Expr::StartText | Expr::EndText
+            | Expr::WordBoundary | Expr::NotWordBoundary
+            | Expr::WordBoundaryAscii | Expr::NotWordBoundaryAscii => {
+                visitor.visit_assertion(expr)
+            }
+            Expr::Group { ref e, .. } => {
+                visitor.visit_group_pre(expr);
+                stack.push(Frame::Post(expr));
+                stack.push(Frame::Pre(e));
+            }
+            Expr::Repeat { ref e, .. } => {
+                visitor.visit_repeat_pre(expr);
+                stack.push(Frame::Post(expr));
+                stack.push(Frame::Pre(e));
+            }
+            Expr::Concat(ref es) => {
+                visitor.visit_concat_pre(expr);
+                stack.push(Frame::Post(expr));
+                stack.extend(es.iter().rev().map(Frame::Pre));
+            }
+            Expr::Alternate(ref es) => {
+                visitor.visit_alternate_pre(expr);
+                stack.push(Frame::Post(expr));
+                stack.extend(es.iter().rev().map(Frame::Pre));
+            }
+        }
+    }
+}
+
+/// A visitor that renders one expression per line, indented by depth.
+///
+/// This is used to implement `Expr::tree_string`.
+pub struct TreePrinter {
+    out: String,
+    depth: usize,
+}
+
+impl TreePrinter {
+    /// Renders `expr` as an indented tree.
+    pub fn print(expr: &Expr) -> String {
+        let mut printer = TreePrinter { out: String::new(), depth: 0 };
+        visit(expr, &mut printer);
+        printer.out
+    }
+
+    fn line(&mut self, label: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(label);
+        self.out.push('\n');
+    }
+
+    fn open(&mut self, label: &str) {
+        self.line(label);
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+impl Visitor for TreePrinter {
+    fn visit_empty(&mut self, e: &Expr) { self.line(&label(e)); }
+    fn visit_literal(&mut self, e: &Expr) { self.line(&label(e)); }
+    fn visit_any(&mut self, e: &Expr) { self.line(&label(e)); }
+    fn visit_class(&mut self, e: &Expr) { self.line(&label(e)); }
+    fn visit_assertion(&mut self, e: &Expr) { self.line(&label(e)); }
+    fn visit_group_pre(&mut self, e: &Expr) { self.open(&label(e)); }
+    fn visit_group_post(&mut self, _: &Expr) { self.close(); }
+    fn visit_repeat_pre(&mut self, e: &Expr) { self.open(&label(e)); }
+    fn visit_repeat_post(&mut self, _: &Expr) { self.close(); }
+    fn visit_concat_pre(&mut self, e: &Expr) { self.open(&label(e)); }
+    fn visit_concat_post(&mut self, _: &Expr) { self.close(); }
+    fn visit_alternate_pre(&mut self, e: &Expr) { self.open(&label(e)); }
+    fn visit_alternate_post(&mut self, _: &Expr) { self.close(); }
+}
+
+/// A visitor that renders an expression as a Graphviz DOT digraph.
+///
+/// This is used to implement `Expr::to_dot`.
+pub struct DotPrinter {
+    out: String,
+    next_id: usize,
+    parents: Vec<usize>,
+}
+
+impl DotPrinter {
+    /// Renders `expr` as a DOT digraph.
+    pub fn print(expr: &Expr) -> String {
+        let mut printer = DotPrinter {
+            out: "digraph expr {\n".to_owned(),
+            next_id: 0,
+            parents: vec![],
+        };
+        visit(expr, &mut printer);
+        printer.out.push_str("}\n");
+        printer.out
+    }
+
+    fn node(&mut self, expr: &Expr) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.out.push_str(&format!("  n{} [label=\"", id));
+        for c in label(expr).chars() {
+            if c == '"' || c == '\\' {
+                self.out.push('\\');
+            }
+            self.out.push(c);
+        }
+        self.out.push_str("\"];\n");
+        if let Some(&parent) = self.parents.last() {
+            self.out.push_str(&format!("  n{} -> n{};\n", parent, id));
+        }
+        id
+    }
+
+    fn open(&mut self, expr: &Expr) {
+        let id = self.node(expr);
+        self.parents.push(id);
+    }
+
+    fn close(&mut self) {
+        self.parents.pop();
+    }
+}
+
+impl Visitor for DotPrinter {
+    fn visit_empty(&mut self, e: &Expr) { self.node(e); }
+    fn visit_literal(&mut self, e: &Expr) { self.node(e); }
+    fn visit_any(&mut self, e: &Expr) { self.node(e); }
+    fn visit_class(&mut self, e: &Expr) { self.node(e); }
+    fn visit_assertion(&mut self, e: &Expr) { self.node(e); }
+    fn visit_group_pre(&mut self, e: &Expr) { self.open(e); }
+    fn visit_group_post(&mut self, _: &Expr) { self.close(); }
+    fn visit_repeat_pre(&mut self, e: &Expr) { self.open(e); }
+    fn visit_repeat_post(&mut self, _: &Expr) { self.close(); }
+    fn visit_concat_pre(&mut self, e: &Expr) { self.open(e); }
+    fn visit_concat_post(&mut self, _: &Expr) { self.close(); }
+    fn visit_alternate_pre(&mut self, e: &Expr) { self.open(e); }
+    fn visit_alternate_post(&mut self, _: &Expr) { self.close(); }
+}
+
+/// Returns a short description of `expr`, not including its
+/// sub-expressions.
+///
+/// Literals and classes are followed by their `Display` form.
+fn label(expr: &Expr) -> String {
+    match *expr {
+        Expr::Empty => "Empty".to_owned(),
+        Expr::Literal { .. } | Expr::LiteralBytes { .. } => {
+            format!("Literal {}", expr)
+        }
+        Expr::Class(_) | Expr::ClassBytes(_) => format!("Class {}", expr),
+        Expr::Group { i: Some(i), name: Some(ref name), .. } => {
+            format!("Group({}, {})", i, name)
+        }
+        Expr::Group { i: Some(i), .. } => format!("Group({})", i),
+        Expr::Group { .. } => "Group".to_owned(),
+        Expr::Repeat { r, greedy: true, .. } => format!("Repeat {}", r),
+        Expr::Repeat { r, .. } => format!("Repeat {}?", r),
+        Expr::Concat(_) => "Concat".to_owned(),
+        Expr::Alternate(_) => "Alternate".to_owned(),
+        _ => format!("{:?}", expr),
+    }
+}
+
+/// An iterator over an expression and all of its sub-expressions in
+/// pre-order.
+///
+/// This is created by `Expr::iter_preorder`. Every expression is yielded
+/// before its sub-expressions, which are yielded in the order they appear in
+/// the expression. The walk uses a heap allocated stack instead of
+/// recursion.
+#[derive(Clone, Debug)]
+pub struct PreorderIter<'a> {
+    stack: Vec<&'a Expr>,
+}
+
+impl<'a> PreorderIter<'a> {
+    /// Creates an iterator that starts at `expr`.
+    pub fn new(expr: &'a Expr) -> PreorderIter<'a> {
+        PreorderIter { stack: vec![expr] }
+    }
+}
+
+impl<'a> Iterator for PreorderIter<'a> {
+    type Item = &'a Expr;
+
+    fn next(&mut self) -> Option<&'a Expr> {
+        let expr = match self.stack.pop() {
+            None => return None,
+            Some(expr) => expr,
+        };
+        match *expr {
+            Expr::Group { ref e, .. } | Expr::Repeat { ref e, .. } => {
+                self.stack.push(e);
+            }
+            Expr::Concat(ref es) | Expr::Alternate(ref es) => {
+                self.stack.extend(es.iter().rev());
+            }
+            _ => {}
+        }
+        Some(expr)
+    }
+}
+
+/// A trait for rewriting an `Expr` with `fold`.
+///
+/// The tree is rebuilt bottom-up: each method receives an expression whose
+/// sub-expressions have already been folded, and returns the expression to
+/// put in its place. Every method returns its argument unchanged by default,
+/// so implementors only need to override the methods for the expressions
+/// they want to rewrite.
+pub trait Folder {
+    /// Called for `Expr::Empty`.
+    fn fold_empty(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::Literal` and `Expr::LiteralBytes`.
+    fn fold_literal(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::AnyChar`, `Expr::AnyCharNoNL`, `Expr::AnyByte` and
+    /// `Expr::AnyByteNoNL`.
+    fn fold_any(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::Class` and `Expr::ClassBytes`.
+    fn fold_class(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for anchors and word boundary assertions.
+    fn fold_assertion(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::Group` after its sub-expression is folded.
+    fn fold_group(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::Repeat` after its sub-expression is folded.
+    fn fold_repeat(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::Concat` after its sub-expressions are folded.
+    fn fold_concat(&mut self, expr: Expr) -> Expr { expr }
+
+    /// Called for `Expr::Alternate` after its branches are folded.
+    fn fold_alternate(&mut self, expr: Expr) -> Expr { expr }
+}
+
+/// Rebuilds `expr` bottom-up, replacing every expression with the result
+/// of the corresponding method on `folder`.
+///
+/// Sub-expressions are folded in the order they appear in the expression.
+pub fn fold<F: Folder>(expr: Expr, folder: &mut F) -> Expr {
+    match expr {
+        Expr::Empty => folder.fold_empty(expr),
+        Expr::Literal { .. } | Expr::LiteralBytes { .. } => {
+            folder.fold_literal(expr)
+        }
+        Expr::AnyChar | Expr::AnyCharNoNL
+        | Expr::AnyByte | Expr::AnyByteNoNL => folder.fold_any(expr),
+        Expr::Class(_) | Expr::ClassBytes(_) => folder.fold_class(expr),
+        Expr::StartLine | Expr::EndLine
+        | Expr::StartText | Expr::EndText
+        | Expr::WordBoundary | Expr::NotWordBoundary
+        | Expr::WordBoundaryAscii | Expr::NotWordBoundaryAscii => {
+            folder.fold_assertion(expr)
+        }
+        Expr::Group { e, i, name } => {
+            let e = Box::new(fold(*e, folder));
+            folder.fold_group(Expr::Group { e: e, i: i, name: name })
+        }
+        Expr::Repeat { e, r, greedy } => {
+            let e = Box::new(fold(*e, folder));
+            folder.fold_repeat(Expr::Repeat { e: e, r: r, greedy: greedy })
+        }
+        Expr::Concat(es) => {
+            let es = es.into_iter().map(|e| fold(e, folder)).collect();
+            folder.fold_concat(Expr::Concat(es))
+        }
+        Expr::Alternate(es) => {
+            let es = es.into_iter().map(|e| fold(e, folder)).collect();
+            folder.fold_alternate(Expr::Alternate(es))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {CharClass, ClassRange, Expr};
+    use super::{Folder, Visitor, fold, visit};
+
+    #[derive(Default)]
+    struct Counter {
+        nodes: usize,
+        literals: Vec<String>,
+        depth: usize,
+        max_depth: usize,
+    }
+
+    impl Counter {
+        fn enter(&mut self) {
+            self.nodes += 1;
+            self.depth += 1;
+            if self.depth > self.max_depth {
+                self.max_depth = self.depth;
+            }
+        }
+    }
+
+    impl Visitor for Counter {
+        fn visit_empty(&mut self, _: &Expr) { self.nodes += 1; }
+        fn visit_any(&mut self, _: &Expr) { self.nodes += 1; }
+        fn visit_class(&mut self, _: &Expr) { self.nodes += 1; }
+        fn visit_assertion(&mut self, _: &Expr) { self.nodes += 1; }
+
+        fn visit_literal(&mut self, expr: &Expr) {
+            self.nodes += 1;
+            self.literals.push(expr.to_string());
+        }
+
+        fn visit_group_pre(&mut self, _: &Expr) { self.enter(); }
+        fn visit_group_post(&mut self, _: &Expr) { self.depth -= 1; }
+        fn visit_repeat_pre(&mut self, _: &Expr) { self.nodes += 1; }
+        fn visit_concat_pre(&mut self, _: &Expr) { self.nodes += 1; }
+        fn visit_alternate_pre(&mut self, _: &Expr) { self.nodes += 1; }
+    }
+
+    fn count(re: &str) -> Counter {
+        let mut counter = Counter::default();
+        visit(&Expr::parse(re).unwrap(), &mut counter);
+        counter
+    }
+
+    #[test]
+    fn count_nodes() {
+        assert_eq!(count("").nodes, 1);
+        assert_eq!(count("a").nodes, 1);
+        // Concat, a, Group, Repeat, Group, Alternate, b, c, Class, EndText
+        assert_eq!(count(r"a((?:b|c)*)\d$").nodes, 10);
+    }
+
+    #[test]
+    fn literals_in_order() {
+        assert_eq!(count("ab(c|d)*e").literals, vec![
+            "(?u:ab)", "(?u:c)", "(?u:d)", "(?u:e)",
+        ]);
+    }
+
+    #[test]
+    fn group_depth() {
+        let counter = count("(a(b(c)))(d)");
+        assert_eq!(counter.max_depth, 3);
+        assert_eq!(counter.depth, 0);
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let depth = 1000;
+        let mut expr = Expr::Literal { chars: vec!['a'], casei: false };
+        for i in 0..depth {
+            expr = Expr::Group { e: Box::new(expr), i: Some(i), name: None };
+        }
+        let mut counter = Counter::default();
+        visit(&expr, &mut counter);
+        assert_eq!(counter.max_depth, depth);
+        assert_eq!(counter.literals, vec!["(?u:a)"]);
+    }
+
+    struct Upper;
+
+    impl Folder for Upper {
+        fn fold_literal(&mut self, expr: Expr) -> Expr {
+            match expr {
+                Expr::Literal { chars, casei } => Expr::Literal {
+                    chars: chars.iter()
+                        .map(|c| c.to_ascii_uppercase())
+                        .collect(),
+                    casei: casei,
+                },
+                e => e,
+            }
+        }
+    }
+
+    #[test]
+    fn fold_uppercase_literals() {
+        let expr = Expr::parse(r"ab(?P<n>c|d)*[e-f]").unwrap();
+        assert_eq!(fold(expr, &mut Upper).to_string(),
+                   "(?u:AB)(?P<n>(?u:C)|(?u:D))*(?u:[e-f])");
+    }
+
+    #[test]
+    fn fold_unchanged() {
+        let expr = Expr::parse(r"^a(b|c)+?\b.$").unwrap();
+        struct Identity;
+        impl Folder for Identity {}
+        assert_eq!(fold(expr.clone(), &mut Identity), expr);
+    }
+
+    #[test]
+    fn fold_dot_to_class() {
+        struct DotToClass;
+        impl Folder for DotToClass {
+            fn fold_any(&mut self, expr: Expr) -> Expr {
+                match expr {
+                    Expr::AnyCharNoNL => Expr::Class(CharClass::new(vec![
+                        ClassRange::new('\x00', '\x09'),
+                        ClassRange::new('\x0B', '\u{10FFFF}'),
+                    ])),
+                    e => e,
+                }
+            }
+        }
+        let expr = Expr::parse("a.(.)").unwrap();
+        assert_eq!(fold(expr, &mut DotToClass),
+                   Expr::parse(r"a[^\n]([^\n])").unwrap());
+    }
+
+    #[test]
+    fn preorder() {
+        let expr = Expr::parse(r"a+(b|c*)[0-9]?").unwrap();
+        let repeats = expr.iter_preorder().filter(|e| match **e {
+            Expr::Repeat { .. } => true,
+            _ => false,
+        }).count();
+        assert_eq!(repeats, 3);
+
+        let printed: Vec<String> = expr.iter_preorder()
+            .filter(|e| match **e {
+                Expr::Literal { .. } | Expr::Repeat { .. } => true,
+                _ => false,
+            })
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(printed, vec![
+            "(?u:a)+", "(?u:a)", "(?u:b)", "(?u:c)*", "(?u:c)",
+            r"(?u:[0-9])?",
+        ]);
+        assert_eq!(expr.iter_preorder().count(),
+                   count(r"a+(b|c*)[0-9]?").nodes);
+    }
+
+    #[test]
+    fn tree_string() {
+        assert_eq!(Expr::parse("a(b|c)*").unwrap().tree_string(), "\
+Concat
+  Literal (?u:a)
+  Repeat *
+    Group(1)
+      Alternate
+        Literal (?u:b)
+        Literal (?u:c)
+");
+        assert_eq!(Expr::parse(r"^(?P<x>.)+?(?:[a-c]|\b)$").unwrap()
+                       .tree_string(), "\
+Concat
+  StartText
+  Repeat +?
+    Group(1, x)
+      AnyCharNoNL
+  Group
+    Alternate
+      Class (?u:[a-c])
+      WordBoundary
+  EndText
+");
+        assert_eq!(Expr::Empty.tree_string(), "Empty\n");
+    }
+
+    #[test]
+    fn to_dot() {
+        let dot = Expr::parse("a(b|c)").unwrap().to_dot();
+        assert_eq!(dot, r#"digraph expr {
+  n0 [label="Concat"];
+  n1 [label="Literal (?u:a)"];
+  n0 -> n1;
+  n2 [label="Group(1)"];
+  n0 -> n2;
+  n3 [label="Alternate"];
+  n2 -> n3;
+  n4 [label="Literal (?u:b)"];
+  n3 -> n4;
+  n5 [label="Literal (?u:c)"];
+  n3 -> n5;
+}
+"#);
+        assert_eq!(dot.matches("[label=").count(), 6);
+        assert_eq!(dot.matches(" -> ").count(), 5);
+
+        let dot = Expr::parse(r#"\\""#).unwrap().to_dot();
+        assert!(dot.contains(r#"[label="Literal (?u:\\\\\")"];"#));
+    }
+}
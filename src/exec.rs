@@ -23,13 +23,13 @@ use error::Error;
 use input::{ByteInput, CharInput};
 use literals::LiteralSearcher;
 use pikevm;
-use prog::Program;
+use prog::{InstPtr, Program};
 use re_builder::RegexOptions;
 use re_bytes;
 use re_set;
 use re_trait::{RegularExpression, Slot, Locations, as_slots};
 use re_unicode;
-use utf8::next_utf8;
+use utf8::{next_utf8, prev_utf8};
 
 /// `Exec` manages the execution of a regular expression.
 ///
@@ -336,6 +336,10 @@ impl<'c> RegularExpression for ExecNoSyncStr<'c> {
         next_utf8(text.as_bytes(), i)
     }
 
+    fn prev_before_empty(&self, text: &str, i: usize) -> usize {
+        prev_utf8(text.as_bytes(), i)
+    }
+
     #[inline(always)] // reduces constant overhead
     fn shortest_match_at(&self, text: &str, start: usize) -> Option<usize> {
         self.0.shortest_match_at(text.as_bytes(), start)
@@ -351,6 +355,42 @@ impl<'c> RegularExpression for ExecNoSyncStr<'c> {
         self.0.find_at(text.as_bytes(), start)
     }
 
+    fn find_in(
+        &self,
+        text: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        assert!(
+            start <= end
+            && text.is_char_boundary(start)
+            && text.is_char_boundary(end),
+            "{}..{} is not a range of character boundaries", start, end);
+        self.0.find_in(text.as_bytes(), start, end)
+    }
+
+    fn find_longest_at(
+        &self,
+        text: &str,
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        self.0.find_longest_at(text.as_bytes(), start)
+    }
+
+    fn match_anchored_at(&self, text: &str, start: usize) -> Option<usize> {
+        self.0.match_anchored_at(text.as_bytes(), start)
+    }
+
+    fn find_before_at(
+        &self,
+        text: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        let bytes = text.as_bytes();
+        self.0.find_before_traced(bytes, start, end, |i| prev_utf8(bytes, i))
+    }
+
     #[inline(always)] // reduces constant overhead
     fn read_captures_at(
         &self,
@@ -376,6 +416,10 @@ impl<'c> RegularExpression for ExecNoSync<'c> {
         i + 1
     }
 
+    fn prev_before_empty(&self, _text: &[u8], i: usize) -> usize {
+        i - 1
+    }
+
     /// Returns the end of a match location, possibly occurring before the
     /// end location of the correct leftmost-first match.
     #[inline(always)] // reduces constant overhead
@@ -513,6 +557,71 @@ impl<'c> RegularExpression for ExecNoSync<'c> {
         }
     }
 
+    fn find_in(
+        &self,
+        text: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        self.find_at(&text[start..end], 0)
+            .map(|(s, e)| (start + s, start + e))
+    }
+
+    /// Finds the start and end location of the leftmost-longest match,
+    /// starting at the given location.
+    ///
+    /// This always runs the Pike VM, so it is much slower than `find_at`.
+    fn find_longest_at(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        if !self.is_anchor_end_match(text) {
+            return None;
+        }
+        match self.ro.match_type {
+            MatchType::Nothing => None,
+            MatchType::DfaMany => {
+                unreachable!("BUG: RegexSet cannot be used with find")
+            }
+            _ => self.find_pikevm(text, start, true, false),
+        }
+    }
+
+    /// Returns the end of the leftmost-first match that begins exactly at
+    /// `start`.
+    ///
+    /// This runs the Pike VM without its implicit `.*?` prefix, so it never
+    /// looks further into the text than a match starting at `start` could
+    /// reach.
+    fn match_anchored_at(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> Option<usize> {
+        if !self.is_anchor_end_match(text) {
+            return None;
+        }
+        match self.ro.match_type {
+            MatchType::Nothing => None,
+            MatchType::DfaMany => {
+                unreachable!("BUG: RegexSet cannot be used with find")
+            }
+            _ => self.find_pikevm(text, start, false, true).map(|(_, e)| e),
+        }
+    }
+
+    /// Finds the last match ending at or before `end` that starts at or
+    /// before `start`.
+    fn find_before_at(
+        &self,
+        text: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        self.find_before_traced(text, start, end, |i| i - 1)
+    }
+
     /// Finds the start and end location of the leftmost-first match and also
     /// fills in all matching capture groups.
     ///
@@ -943,6 +1052,8 @@ impl<'c> ExecNoSync<'c> {
                 matches,
                 slots,
                 quit_after_match,
+                false,
+                false,
                 ByteInput::new(text, self.ro.nfa.only_utf8),
                 start)
         } else {
@@ -952,6 +1063,126 @@ impl<'c> ExecNoSync<'c> {
                 matches,
                 slots,
                 quit_after_match,
+                false,
+                false,
+                CharInput::new(text),
+                start)
+        }
+    }
+
+    /// Like find_nfa, but always runs the Pike VM, which can also find the
+    /// leftmost-longest match or only a match beginning at `start`.
+    fn find_pikevm(
+        &self,
+        text: &[u8],
+        start: usize,
+        longest: bool,
+        anchored: bool,
+    ) -> Option<(usize, usize)> {
+        let mut slots = [None, None];
+        let matched = if self.ro.nfa.uses_bytes() {
+            pikevm::Fsm::exec(
+                &self.ro.nfa,
+                self.cache,
+                &mut [false],
+                &mut slots,
+                false,
+                longest,
+                anchored,
+                ByteInput::new(text, self.ro.nfa.only_utf8),
+                start)
+        } else {
+            pikevm::Fsm::exec(
+                &self.ro.nfa,
+                self.cache,
+                &mut [false],
+                &mut slots,
+                false,
+                longest,
+                anchored,
+                CharInput::new(text),
+                start)
+        };
+        if !matched {
+            return None;
+        }
+        match (slots[0], slots[1]) {
+            (Some(s), Some(e)) => Some((s, e)),
+            _ => None,
+        }
+    }
+
+    /// Implements `find_before_at`, using `prev` to step back from one
+    /// starting position to the one before it.
+    ///
+    /// Every anchored search is traced, so a search from an earlier position
+    /// stops as soon as it reaches a state the previous one was in, since it
+    /// must end the same way from then on.
+    fn find_before_traced<F: Fn(usize) -> usize>(
+        &self,
+        text: &[u8],
+        start: usize,
+        end: usize,
+        prev: F,
+    ) -> Option<(usize, usize)> {
+        if !self.is_anchor_end_match(text) {
+            return None;
+        }
+        match self.ro.match_type {
+            MatchType::Nothing => return None,
+            MatchType::DfaMany => {
+                unreachable!("BUG: RegexSet cannot be used with find")
+            }
+            _ => {}
+        }
+        // Every search that fell in step with an earlier one failed too.
+        let mut trace = HashMap::new();
+        let mut s = start;
+        let e = loop {
+            match self.exec_pikevm_traced(&mut trace, text, s) {
+                pikevm::Traced::End(Some(e)) if e <= end => break e,
+                _ => {}
+            }
+            if s == 0 {
+                return None;
+            }
+            s = prev(s);
+        };
+        // Now every search that falls in step with an earlier one ends at
+        // `e`, starting with the one that was just found.
+        trace.clear();
+        self.exec_pikevm_traced(&mut trace, text, s);
+        while s > 0 {
+            let before = prev(s);
+            match self.exec_pikevm_traced(&mut trace, text, before) {
+                pikevm::Traced::Same => {}
+                pikevm::Traced::End(Some(be)) if be == e => {}
+                pikevm::Traced::End(_) => break,
+            }
+            s = before;
+        }
+        Some((s, e))
+    }
+
+    /// Runs the Pike VM with `pikevm::Fsm::exec_traced`.
+    fn exec_pikevm_traced(
+        &self,
+        trace: &mut HashMap<usize, (Vec<InstPtr>, Slot)>,
+        text: &[u8],
+        start: usize,
+    ) -> pikevm::Traced {
+        if self.ro.nfa.uses_bytes() {
+            pikevm::Fsm::exec_traced(
+                &self.ro.nfa,
+                self.cache,
+                trace,
+                ByteInput::new(text, self.ro.nfa.only_utf8),
+                start)
+        } else {
+            pikevm::Fsm::exec_traced(
+                &self.ro.nfa,
+                self.cache,
+                trace,
                 CharInput::new(text),
                 start)
         }
@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::str;
 
 use memchr::memchr;
 
 use re_bytes;
+use re_trait::Slot;
 use re_unicode;
 
 pub fn expand_str(
@@ -89,6 +91,80 @@ pub fn expand_bytes(
     dst.extend(replacement);
 }
 
+/// Determines how `expand_slots` handles a reference to a capture group that
+/// doesn't exist.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnknownRef {
+    /// Expand the reference to the empty string, like `Captures::expand`.
+    Empty,
+    /// Stop expanding and report the name or number of the group.
+    Error,
+}
+
+/// Expands all instances of `$name` in `replacement` using capture slots
+/// from a match in `text`, and writes them to `dst`.
+///
+/// Slot `2 * i` and `2 * i + 1` must hold the start and end of group `i`,
+/// and `names` must map capture group names to their index. References
+/// are written as `$N`, `${N}`, `$name` or `${name}`, and `$$` is a literal
+/// `$`. A group that exists but didn't participate in the match expands to
+/// the empty string. A group that doesn't exist is handled according to
+/// `unknown`. When it is `UnknownRef::Error`, the name (or number) of the
+/// first unknown group is returned and `dst` is left partially written.
+pub fn expand_slots(
+    text: &str,
+    slots: &[Slot],
+    names: &HashMap<String, usize>,
+    mut replacement: &str,
+    dst: &mut String,
+    unknown: UnknownRef,
+) -> Result<(), String> {
+    while !replacement.is_empty() {
+        match memchr(b'$', replacement.as_bytes()) {
+            None => break,
+            Some(i) => {
+                dst.push_str(&replacement[..i]);
+                replacement = &replacement[i..];
+            }
+        }
+        if replacement.as_bytes().get(1).map_or(false, |&b| b == b'$') {
+            dst.push_str("$");
+            replacement = &replacement[2..];
+            continue;
+        }
+        debug_assert!(!replacement.is_empty());
+        let cap_ref = match find_cap_ref(replacement) {
+            Some(cap_ref) => cap_ref,
+            None => {
+                dst.push_str("$");
+                replacement = &replacement[1..];
+                continue;
+            }
+        };
+        replacement = &replacement[cap_ref.end..];
+        let i = match cap_ref.cap {
+            Ref::Number(i) if i < slots.len() / 2 => Some(i),
+            Ref::Number(_) => None,
+            Ref::Named(name) => names.get(name).cloned(),
+        };
+        let i = match (i, unknown) {
+            (Some(i), _) => i,
+            (None, UnknownRef::Empty) => continue,
+            (None, UnknownRef::Error) => {
+                return Err(match cap_ref.cap {
+                    Ref::Number(i) => i.to_string(),
+                    Ref::Named(name) => name.to_owned(),
+                });
+            }
+        };
+        if let (Some(s), Some(e)) = (slots[i * 2], slots[i * 2 + 1]) {
+            dst.push_str(&text[s..e]);
+        }
+    }
+    dst.push_str(replacement);
+    Ok(())
+}
+
 /// `CaptureRef` represents a reference to a capture group inside some text.
 /// The reference is either a capture group name or a number.
 ///
@@ -176,7 +252,9 @@ fn is_valid_cap_letter(b: &u8) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{CaptureRef, find_cap_ref};
+    use std::collections::HashMap;
+
+    use super::{CaptureRef, UnknownRef, expand_slots, find_cap_ref};
 
     macro_rules! find {
         ($name:ident, $text:expr) => {
@@ -212,4 +290,44 @@ mod tests {
     find!(find_cap_ref11, "$");
     find!(find_cap_ref12, " ");
     find!(find_cap_ref13, "");
+
+    fn expand(rep: &str, unknown: UnknownRef) -> Result<String, String> {
+        // A match of `(?P<a>\w+)-(\d+)?(?P<c>x)?` in "foo-bar-".
+        let text = "foo-bar-";
+        let slots = vec![Some(4), Some(8), Some(4), Some(7), None, None,
+                         None, None];
+        let mut names = HashMap::new();
+        names.insert("a".to_owned(), 1);
+        names.insert("c".to_owned(), 3);
+        let mut dst = String::new();
+        expand_slots(text, &slots, &names, rep, &mut dst, unknown)
+            .map(|_| dst)
+    }
+
+    #[test]
+    fn expand_slots_numbered() {
+        let got = expand("$0|${1}x|$2|$3", UnknownRef::Error);
+        assert_eq!(got, Ok("bar-|barx||".to_owned()));
+    }
+
+    #[test]
+    fn expand_slots_named() {
+        assert_eq!(expand("<$a><${a}><$c>", UnknownRef::Error),
+                   Ok("<bar><bar><>".to_owned()));
+    }
+
+    #[test]
+    fn expand_slots_escape() {
+        assert_eq!(expand("$$a $$$a ${ $", UnknownRef::Error),
+                   Ok("$a $bar ${ $".to_owned()));
+    }
+
+    #[test]
+    fn expand_slots_unknown() {
+        assert_eq!(expand("[$zz][$4][$1x]", UnknownRef::Empty),
+                   Ok("[][][]".to_owned()));
+        assert_eq!(expand("[$a][$zz]", UnknownRef::Error),
+                   Err("zz".to_owned()));
+        assert_eq!(expand("${4}", UnknownRef::Error), Err("4".to_owned()));
+    }
 }
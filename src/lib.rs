@@ -526,8 +526,8 @@ pub use re_set::unicode::*;
 pub use re_trait::Locations;
 pub use re_unicode::{
     Regex, Match, Captures,
-    CaptureNames, Matches, CaptureMatches, SubCaptureMatches,
-    Replacer, NoExpand, Split, SplitN,
+    CaptureNames, Matches, ByteRanges, CaptureMatches, SubCaptureMatches,
+    Replacer, NoExpand, Split, SplitN, SplitKeep, SplitPiece,
     escape,
 };
 
@@ -659,9 +659,14 @@ mod sparse;
 pub mod internal {
     pub use compile::Compiler;
     pub use exec::{Exec, ExecBuilder};
+    pub use expand::{UnknownRef, expand_slots};
     pub use input::{Char, Input, CharInput, InputAt};
     pub use literals::LiteralSearcher;
     pub use prog::{Program, Inst, EmptyLook, InstRanges};
     pub use re_plugin::Plugin;
+    pub use re_trait::{
+        CaptureGroup, RegularExpression, Slot, Tokenizer, advance_empty,
+        byte_to_char_offset, slots_to_groups,
+    };
     pub use re_unicode::_Regex;
 }
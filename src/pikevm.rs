@@ -25,6 +25,7 @@
 // Therefore, the Pike VM is generally treated as the fallback when the other
 // matching engines either aren't feasible to run or are insufficient.
 
+use std::collections::HashMap;
 use std::mem;
 
 use exec::ProgramCache;
@@ -99,12 +100,23 @@ impl<'r, I: Input> Fsm<'r, I> {
     ///
     /// If there's a match, `exec` returns `true` and populates the given
     /// captures accordingly.
+    ///
+    /// When `longest` is true, the leftmost-longest match is found instead of
+    /// the leftmost-first match. This requires at least two slots so that
+    /// the start of every thread is tracked, and the captures it reports
+    /// beyond the overall match are unspecified.
+    ///
+    /// When `anchored` is true, only matches beginning exactly at `start` are
+    /// found, and the search stops as soon as no thread started there is
+    /// still alive.
     pub fn exec(
         prog: &'r Program,
         cache: &ProgramCache,
         matches: &mut [bool],
         slots: &mut [Slot],
         quit_after_match: bool,
+        longest: bool,
+        anchored: bool,
         input: I,
         start: usize,
     ) -> bool {
@@ -123,6 +135,8 @@ impl<'r, I: Input> Fsm<'r, I> {
             matches,
             slots,
             quit_after_match,
+            longest,
+            anchored,
             at,
         )
     }
@@ -134,8 +148,11 @@ impl<'r, I: Input> Fsm<'r, I> {
         matches: &mut [bool],
         slots: &mut [Slot],
         quit_after_match: bool,
+        longest: bool,
+        anchored: bool,
         mut at: InputAt,
     ) -> bool {
+        let start = at.pos();
         let mut matched = false;
         let mut all_matched = false;
         clist.set.clear();
@@ -150,18 +167,20 @@ impl<'r, I: Input> Fsm<'r, I> {
                 //    looking for matches for multiple regexes, unless we know
                 //    they all matched.)
                 //
-                // 2. If the expression starts with a '^' we can terminate as
-                //    soon as the last thread dies.
+                // 2. If the expression starts with a '^', or the caller asked
+                //    for an anchored search, we can terminate as soon as the
+                //    last thread dies.
                 if (matched && matches.len() <= 1)
                     || all_matched
-                    || (!at.is_start() && self.prog.is_anchored_start) {
+                    || (!at.is_start() && self.prog.is_anchored_start)
+                    || (anchored && at.pos() > start) {
                     break;
                 }
 
                 // 3. If there's a literal prefix for the program, try to
                 //    jump ahead quickly. If it can't be found, then we can
                 //    bail out early.
-                if !self.prog.prefixes.is_empty() {
+                if !anchored && !self.prog.prefixes.is_empty() {
                     at = match self.input.prefix_at(&self.prog.prefixes, at) {
                         None => break,
                         Some(at) => at,
@@ -172,7 +191,11 @@ impl<'r, I: Input> Fsm<'r, I> {
             // This simulates a preceding '.*?' for every regex by adding
             // a state starting at the current position in the input for the
             // beginning of the program only if we don't already have a match.
-            if clist.set.is_empty()
+            if anchored {
+                if at.pos() == start {
+                    self.add(&mut clist, slots, 0, at);
+                }
+            } else if clist.set.is_empty()
                 || (!self.prog.is_anchored_start && !all_matched) {
                 self.add(&mut clist, slots, 0, at);
             }
@@ -181,8 +204,23 @@ impl<'r, I: Input> Fsm<'r, I> {
             // we can to look at the current character, so we advance the
             // input.
             let at_next = self.input.at(at.next_pos());
+            let mut step_matched = false;
             for i in 0..clist.set.len() {
                 let ip = clist.set[i];
+                if longest && matched {
+                    // Threads are ordered by where they started, so once we
+                    // have a match, a thread that started later can't yield
+                    // the leftmost match. A lower priority thread that
+                    // started at the same place can still yield a longer
+                    // match on a later step, but not on this one.
+                    let thread_start = clist.caps(ip)[0];
+                    if thread_start > slots[0]
+                        || (step_matched
+                            && thread_start == slots[0]
+                            && self.prog[ip].is_match()) {
+                        continue;
+                    }
+                }
                 if self.step(
                     &mut nlist,
                     matches,
@@ -193,13 +231,14 @@ impl<'r, I: Input> Fsm<'r, I> {
                     at_next,
                 ) {
                     matched = true;
+                    step_matched = true;
                     all_matched = all_matched || matches.iter().all(|&b| b);
                     if quit_after_match {
                         // If we only care if a match occurs (not its
                         // position), then we can quit right now.
                         break 'LOOP;
                     }
-                    if self.prog.matches.len() == 1 {
+                    if self.prog.matches.len() == 1 && !longest {
                         // We don't need to check the rest of the threads
                         // in this set because we've matched something
                         // ("leftmost-first"). However, we still need to check
@@ -222,6 +261,83 @@ impl<'r, I: Input> Fsm<'r, I> {
         matched
     }
 
+    /// Runs an anchored leftmost-first search from `start`, like `exec` with
+    /// `anchored` set, and returns the end of the match.
+    ///
+    /// `trace` records, for each position reached, the threads alive there
+    /// and the end of the match found so far. Two searches that agree on
+    /// both at the same position find the same match end from then on, so
+    /// if this search reaches a state already in `trace`, it stops and
+    /// returns `Traced::Same`. Otherwise it adds its own states to `trace`.
+    /// This lets a caller that searches from successively earlier positions
+    /// stop each search as soon as it falls in step with the previous one.
+    pub fn exec_traced(
+        prog: &'r Program,
+        cache: &ProgramCache,
+        trace: &mut HashMap<usize, (Vec<InstPtr>, Slot)>,
+        input: I,
+        start: usize,
+    ) -> Traced {
+        let mut cache = cache.borrow_mut();
+        let cache = &mut cache.pikevm;
+        cache.clist.resize(prog.len(), prog.captures.len());
+        cache.nlist.resize(prog.len(), prog.captures.len());
+        let at = input.at(start);
+        Fsm {
+            prog: prog,
+            stack: &mut cache.stack,
+            input: input,
+        }.exec_traced_(&mut cache.clist, &mut cache.nlist, trace, at)
+    }
+
+    fn exec_traced_(
+        &mut self,
+        mut clist: &mut Threads,
+        mut nlist: &mut Threads,
+        trace: &mut HashMap<usize, (Vec<InstPtr>, Slot)>,
+        mut at: InputAt,
+    ) -> Traced {
+        let mut slots = [None, None];
+        clist.set.clear();
+        nlist.set.clear();
+        if self.prog.is_anchored_start && !at.is_start() {
+            return Traced::End(None);
+        }
+        self.add(&mut clist, &mut [None, None], 0, at);
+        loop {
+            let at_next = self.input.at(at.next_pos());
+            for i in 0..clist.set.len() {
+                let ip = clist.set[i];
+                if self.step(
+                    &mut nlist,
+                    &mut [false],
+                    &mut slots,
+                    clist.caps(ip),
+                    ip,
+                    at,
+                    at_next,
+                ) {
+                    break;
+                }
+            }
+            if at.is_end() {
+                break;
+            }
+            at = at_next;
+            mem::swap(clist, nlist);
+            nlist.set.clear();
+            if clist.set.is_empty() {
+                break;
+            }
+            let state = (clist.set.to_vec(), slots[1]);
+            if trace.get(&at.pos()) == Some(&state) {
+                return Traced::Same;
+            }
+            trace.insert(at.pos(), state);
+        }
+        Traced::End(slots[1])
+    }
+
     /// Step through the input, one token (byte or codepoint) at a time.
     ///
     /// nlist is the set of states that will be processed on the next token
@@ -352,6 +468,17 @@ impl<'r, I: Input> Fsm<'r, I> {
     }
 }
 
+/// The outcome of `Fsm::exec_traced`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Traced {
+    /// The search reached a state recorded by an earlier search, so it ends
+    /// the same way that one did.
+    Same,
+    /// The search finished on its own, with the end of the match it found,
+    /// if any.
+    End(Option<usize>),
+}
+
 impl Threads {
     fn new() -> Self {
         Threads {
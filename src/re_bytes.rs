@@ -351,6 +351,49 @@ impl Regex {
         }
     }
 
+    /// Returns an iterator over the pieces of `text` when it is split by
+    /// matches of the regular expression, including the matches themselves.
+    ///
+    /// Each element is either a `SplitPiece::Field`, which corresponds to an
+    /// element yielded by `split`, or a `SplitPiece::Delim`, which
+    /// corresponds to the match that ended the preceding field. Fields and
+    /// delimiters are yielded in the order in which they appear, so
+    /// concatenating every piece reproduces `text`.
+    ///
+    /// A delimiter at the start of `text` is preceded by an empty field, and
+    /// an empty match yields an empty delimiter. As with `split`, no empty
+    /// field is yielded after a delimiter that ends `text`.
+    ///
+    /// This method will *not* copy the text given.
+    ///
+    /// # Example
+    ///
+    /// Tokenize arithmetic while keeping the operators:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::bytes::{Regex, SplitPiece};
+    /// # fn main() {
+    /// let re = Regex::new(r"\s*[-+]\s*").unwrap();
+    /// let pieces: Vec<(bool, &[u8])> = re.split_keep(b"1 + 2-3").map(|p| {
+    ///     match p {
+    ///         SplitPiece::Field(m) => (false, m.as_bytes()),
+    ///         SplitPiece::Delim(m) => (true, m.as_bytes()),
+    ///     }
+    /// }).collect();
+    /// assert_eq!(pieces, vec![
+    ///     (false, &b"1"[..]), (true, &b" + "[..]), (false, &b"2"[..]),
+    ///     (true, &b"-"[..]), (false, &b"3"[..]),
+    /// ]);
+    /// # }
+    /// ```
+    pub fn split_keep<'r, 't>(&'r self, text: &'t [u8]) -> SplitKeep<'r, 't> {
+        SplitKeep {
+            finder: self.find_iter(text),
+            last: 0,
+            delim: None,
+        }
+    }
+
     /// Replaces the leftmost-first match with the replacement provided. The
     /// replacement can be a regular byte string (where `$N` and `$name` are
     /// expanded to match capture groups) or a function that takes the matches'
@@ -664,6 +707,10 @@ impl<'r, 't> Iterator for Matches<'r, 't> {
         let text = self.0.text();
         self.0.next().map(|(s, e)| Match::new(text, s, e))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
 }
 
 /// An iterator that yields all non-overlapping capture groups matching a
@@ -675,6 +722,17 @@ impl<'r, 't> Iterator for Matches<'r, 't> {
 /// lifetime of the matched byte string.
 pub struct CaptureMatches<'r, 't>(re_trait::CaptureMatches<'t, ExecNoSync<'r>>);
 
+impl<'r, 't> CaptureMatches<'r, 't> {
+    /// Returns the start position of the next match without computing its
+    /// capture groups.
+    ///
+    /// The match found is remembered, so a subsequent call to `next` only
+    /// needs to resolve the capture groups of that match.
+    pub fn peek_start(&mut self) -> Option<usize> {
+        self.0.peek_start()
+    }
+}
+
 impl<'r, 't> Iterator for CaptureMatches<'r, 't> {
     type Item = Captures<'t>;
 
@@ -685,6 +743,10 @@ impl<'r, 't> Iterator for CaptureMatches<'r, 't> {
             named_groups: self.0.regex().capture_name_idx().clone(),
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
 }
 
 /// Yields all substrings delimited by a regular expression match.
@@ -748,6 +810,74 @@ impl<'r, 't> Iterator for SplitN<'r, 't> {
     }
 }
 
+/// A piece of text yielded by `SplitKeep`.
+///
+/// `'t` is the lifetime of the byte string being split.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SplitPiece<'t> {
+    /// Bytes that aren't matched by the regular expression.
+    Field(Match<'t>),
+    /// Bytes matched by the regular expression.
+    Delim(Match<'t>),
+}
+
+impl<'t> SplitPiece<'t> {
+    /// Returns true if and only if this piece was matched by the regular
+    /// expression.
+    pub fn is_delim(&self) -> bool {
+        match *self {
+            SplitPiece::Field(_) => false,
+            SplitPiece::Delim(_) => true,
+        }
+    }
+
+    /// Returns the location of this piece in the haystack.
+    pub fn as_match(&self) -> Match<'t> {
+        match *self {
+            SplitPiece::Field(m) | SplitPiece::Delim(m) => m,
+        }
+    }
+}
+
+/// Yields all substrings delimited by a regular expression match, along with
+/// the matches themselves.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the byte string being split.
+pub struct SplitKeep<'r, 't> {
+    finder: Matches<'r, 't>,
+    last: usize,
+    delim: Option<Match<'t>>,
+}
+
+impl<'r, 't> Iterator for SplitKeep<'r, 't> {
+    type Item = SplitPiece<'t>;
+
+    fn next(&mut self) -> Option<SplitPiece<'t>> {
+        if let Some(m) = self.delim.take() {
+            return Some(SplitPiece::Delim(m));
+        }
+        let text = self.finder.0.text();
+        match self.finder.next() {
+            None => {
+                if self.last >= text.len() {
+                    None
+                } else {
+                    let s = Match::new(text, self.last, text.len());
+                    self.last = text.len();
+                    Some(SplitPiece::Field(s))
+                }
+            }
+            Some(m) => {
+                let field = Match::new(text, self.last, m.start());
+                self.last = m.end();
+                self.delim = Some(m);
+                Some(SplitPiece::Field(field))
+            }
+        }
+    }
+}
+
 /// An iterator over the names of all possible captures.
 ///
 /// `None` indicates an unnamed capture; the first element (capture 0, the
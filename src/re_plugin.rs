@@ -9,10 +9,15 @@
 // except according to those terms.
 
 use re_trait::{RegularExpression, Slot, Locations, as_slots};
+use utf8::prev_utf8;
 
 /// Plugin is the compiler plugin's data structure. It declare some static
 /// data (like capture groups and the original regex string), but defines its
 /// matching engine as a simple function.
+///
+/// The generated matcher only finds leftmost-first matches, so
+/// `find_longest_at` falls back to the trait's default and may report a
+/// shorter match than the leftmost-longest one.
 #[doc(hidden)]
 pub struct Plugin {
     #[doc(hidden)]
@@ -57,6 +62,10 @@ impl RegularExpression for Plugin {
         i + inc
     }
 
+    fn prev_before_empty(&self, text: &str, i: usize) -> usize {
+        prev_utf8(text.as_bytes(), i)
+    }
+
     fn shortest_match_at(&self, text: &str, start: usize) -> Option<usize> {
         self.find_at(text, start).map(|(_, e)| e)
     }
@@ -74,6 +83,21 @@ impl RegularExpression for Plugin {
         }
     }
 
+    fn find_in(
+        &self,
+        text: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        assert!(
+            start <= end
+            && text.is_char_boundary(start)
+            && text.is_char_boundary(end),
+            "{}..{} is not a range of character boundaries", start, end);
+        self.find_at(&text[start..end], 0)
+            .map(|(s, e)| (start + s, start + e))
+    }
+
     fn read_captures_at<'t>(
         &self,
         locs: &mut Locations,
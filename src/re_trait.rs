@@ -8,11 +8,57 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::borrow::Cow;
+use std::vec;
+
+use utf8::decode_utf8;
+
 /// Slot is a single saved capture location. Note that there are two slots for
 /// every capture in a regular expression (one slot each for the start and end
 /// of the capture).
+///
+/// For the capture group at index `i`, slot `2 * i` holds its start and slot
+/// `2 * i + 1` holds its end. Group `0` is always the entire match.
 pub type Slot = Option<usize>;
 
+/// A single capture group that participated in a match.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CaptureGroup {
+    /// The index of the capture group, where `0` is the entire match.
+    pub index: usize,
+    /// The start offset of the group's match.
+    pub start: usize,
+    /// The end offset of the group's match.
+    pub end: usize,
+}
+
+/// Returns the position after the character at `i` in a byte haystack.
+///
+/// If a valid UTF-8 encoded code point begins at `i`, this skips all of it.
+/// Otherwise, including when `i` is at or past the end of `text`, this
+/// skips a single byte. Custom iterators can use this to step past empty
+/// matches the same way the built in iterators do for `&str`.
+pub fn advance_empty(text: &[u8], i: usize) -> usize {
+    match text.get(i..).and_then(decode_utf8) {
+        Some((_, len)) => i + len,
+        None => i + 1,
+    }
+}
+
+/// Converts capture slots into the capture groups that participated in the
+/// match, in order of their index.
+///
+/// Groups whose start or end slot is `None` are skipped.
+pub fn slots_to_groups(slots: &[Slot]) -> Vec<CaptureGroup> {
+    let mut groups = vec![];
+    for (i, pair) in slots.chunks(2).enumerate() {
+        if let (Some(&Some(s)), Some(&Some(e))) = (pair.get(0), pair.get(1)) {
+            groups.push(CaptureGroup { index: i, start: s, end: e });
+        }
+    }
+    groups
+}
+
 /// Locations represents the offsets of each capturing group in a regex for
 /// a single match.
 ///
@@ -111,11 +157,23 @@ pub trait RegularExpression: Sized {
 
     /// Returns the position of the next character after `i`.
     ///
+    /// After an empty match at `i`, the match iterators start the next
+    /// search here, so that they make progress without splitting a
+    /// character. `i` may be equal to the length of the text, in which case
+    /// the position returned must be greater than the length.
+    ///
     /// For example, a haystack with type `&[u8]` probably returns `i+1`,
     /// whereas a haystack with type `&str` probably returns `i` plus the
-    /// length of the next UTF-8 sequence.
+    /// length of the next UTF-8 sequence. `advance_empty` is a version of
+    /// this for byte haystacks that may contain UTF-8.
     fn next_after_empty(&self, text: &Self::Text, i: usize) -> usize;
 
+    /// Returns the position of the previous character before `i`.
+    ///
+    /// This is the inverse of `next_after_empty`. `i` is always greater
+    /// than `0`.
+    fn prev_before_empty(&self, text: &Self::Text, i: usize) -> usize;
+
     /// Returns the location of the shortest match.
     fn shortest_match_at(
         &self,
@@ -137,8 +195,131 @@ pub trait RegularExpression: Sized {
         start: usize,
     ) -> Option<(usize, usize)>;
 
+    /// Returns the leftmost-first match within `text[start..end]`, if one
+    /// exists.
+    ///
+    /// The search behaves as if `text[start..end]` were the entire haystack,
+    /// so a match never extends past `end`, and assertions like `^`, `$` and
+    /// `\b` treat `start` and `end` as the edges of the text. The offsets
+    /// returned are relative to all of `text`.
+    ///
+    /// This panics if `start..end` is not a valid range of `text`. For `str`
+    /// haystacks, both `start` and `end` must also lie on character
+    /// boundaries.
+    fn find_in(
+        &self,
+        text: &Self::Text,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)>;
+
+    /// Returns the leftmost-longest match location if one exists.
+    ///
+    /// Where `find_at` reports the match preferred by the order of
+    /// alternations and the greediness of repetitions, this reports the
+    /// longest of all matches that begin at the leftmost possible position,
+    /// as POSIX does. For example, `a|ab` matches `a` in `ab` with `find_at`
+    /// but matches `ab` with this method. Both methods always agree on where
+    /// the match starts.
+    ///
+    /// Implementations may be considerably slower than `find_at`.
+    ///
+    /// The default implementation reports the leftmost-first match, which is
+    /// only the longest one for patterns where the two agree. Searching
+    /// successively shorter prefixes with `find_in` doesn't help: the
+    /// leftmost-first match still fits in every longer prefix and is still
+    /// preferred there, so a longer result could only come from an assertion
+    /// misjudging the end of the prefix (`ab\b|a` would report `ab` in
+    /// `abc`). Engines should therefore override this. The engines in this
+    /// crate do so by running the Pike VM in leftmost-longest mode.
+    fn find_longest_at(
+        &self,
+        text: &Self::Text,
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        self.find_at(text, start)
+    }
+
+    /// Returns the end of the leftmost-first match that begins exactly at
+    /// `start`, if there is one.
+    ///
+    /// Unlike `find_at` and `is_match_at`, which succeed if a match begins
+    /// anywhere at or after `start`, this fails when the only matches begin
+    /// later in the text. Since a match at `start` is always the leftmost
+    /// one, this reports the same end as `find_at` whenever it succeeds.
+    ///
+    /// The default implementation runs an unanchored search, so when there
+    /// is no match at `start` it may look through the rest of the text before
+    /// giving up. The engines in this crate override it with an anchored
+    /// search that stops as soon as no match beginning at `start` remains
+    /// possible.
+    fn match_anchored_at(
+        &self,
+        text: &Self::Text,
+        start: usize,
+    ) -> Option<usize> {
+        match self.find_at(text, start) {
+            Some((s, e)) if s == start => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the last match ending at or before `end`, if one exists.
+    ///
+    /// The match at a given starting position is the one `match_anchored_at`
+    /// reports there. Starting positions are tried from `end` backwards until
+    /// one has a match ending at or before `end`. The start then keeps moving
+    /// backwards for as long as the match found there ends at the same
+    /// position, so that `a+` matches all of `aaa` rather than just the last
+    /// `a`.
+    fn find_before(
+        &self,
+        text: &Self::Text,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        self.find_before_at(text, end, end)
+    }
+
+    /// Like `find_before`, but only tries starting positions at or before
+    /// `start`.
+    ///
+    /// The default implementation runs `match_anchored_at` at every position
+    /// it tries, which can take time quadratic in the distance moved, e.g.,
+    /// when `a+b` is tried over a long run of `a`. The engines in this crate
+    /// override it so that each search stops as soon as it falls in step with
+    /// the one before it, which makes the whole scan take linear time.
+    fn find_before_at(
+        &self,
+        text: &Self::Text,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        let mut s = start;
+        let mut found = loop {
+            match self.match_anchored_at(text, s) {
+                Some(e) if e <= end => break (s, e),
+                _ => {}
+            }
+            if s == 0 {
+                return None;
+            }
+            s = self.prev_before_empty(text, s);
+        };
+        while found.0 > 0 {
+            let s = self.prev_before_empty(text, found.0);
+            if self.match_anchored_at(text, s) != Some(found.1) {
+                break;
+            }
+            found.0 = s;
+        }
+        Some(found)
+    }
+
     /// Returns the leftmost-first match location if one exists, and also
     /// fills in any matching capture slot locations.
+    ///
+    /// `locs` must have `slots_len()` slots, laid out as described on `Slot`.
+    /// Slots for groups that did not participate in the match are `None`.
     fn read_captures_at(
         &self,
         locs: &mut Locations,
@@ -160,17 +341,213 @@ pub trait RegularExpression: Sized {
         }
     }
 
+    /// Returns the `n`th (counting from `0`) of all non-overlapping
+    /// successive leftmost-first matches, if there are that many.
+    ///
+    /// This finds the same match as `find_iter(text).nth(n)`.
+    fn nth_match(
+        &self,
+        text: &Self::Text,
+        n: usize,
+    ) -> Option<(usize, usize)> where Self::Text: AsRef<[u8]> {
+        let (mut last_end, mut last_match) = (0, None);
+        for _ in 0..n {
+            if next_match(self, text, &mut last_end, &mut last_match)
+                .is_none() {
+                return None;
+            }
+        }
+        next_match(self, text, &mut last_end, &mut last_match)
+    }
+
+    /// Returns the last of all non-overlapping successive leftmost-first
+    /// matches, if there are any.
+    ///
+    /// This finds the same match as `find_iter(text).last()`.
+    fn last_match(
+        &self,
+        text: &Self::Text,
+    ) -> Option<(usize, usize)> where Self::Text: AsRef<[u8]> {
+        let (mut last_end, mut last_match) = (0, None);
+        let mut last = None;
+        while let Some(m) =
+                next_match(self, text, &mut last_end, &mut last_match) {
+            last = Some(m);
+        }
+        last
+    }
+
+    /// Returns the number of non-overlapping successive leftmost-first
+    /// matches.
+    ///
+    /// This counts the same matches as `find_iter(text).count()`, without
+    /// allocating.
+    fn match_count(
+        &self,
+        text: &Self::Text,
+    ) -> usize where Self::Text: AsRef<[u8]> {
+        let (mut last_end, mut last_match) = (0, None);
+        let mut count = 0;
+        while next_match(self, text, &mut last_end, &mut last_match)
+                .is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Finds all non-overlapping successive leftmost-first matches up front
+    /// and returns an iterator over them.
+    ///
+    /// The matches are the same as those of `find_iter`. Collecting them
+    /// first costs memory proportional to the number of matches, but the
+    /// iterator knows its length and can be reversed.
+    fn matches_collected(
+        self,
+        text: &Self::Text,
+    ) -> CollectedMatches where Self::Text: AsRef<[u8]> {
+        CollectedMatches(self.find_iter(text).collect::<Vec<_>>().into_iter())
+    }
+
+    /// Returns an iterator over all non-overlapping successive leftmost-first
+    /// matches, with offsets counted in characters rather than bytes.
+    fn find_iter_chars(
+        self,
+        text: &str,
+    ) -> CharMatches<Self> where Self: RegularExpression<Text=str> {
+        CharMatches { it: self.find_iter(text), byte: 0, chars: 0 }
+    }
+
+    /// Returns an iterator over all non-overlapping successive leftmost-first
+    /// matches, yielding the matched slices of `text` instead of offsets.
+    fn find_iter_str(
+        self,
+        text: &str,
+    ) -> StrMatches<Self> where Self: RegularExpression<Text=str> {
+        StrMatches(self.find_iter(text))
+    }
+
+    /// Returns an iterator over the pieces of `text` with every
+    /// non-overlapping match replaced by `replacement`.
+    ///
+    /// Pieces alternate between unmatched text and the replacement, starting
+    /// and ending with unmatched text (which may be empty), so concatenating
+    /// them gives the fully replaced string. The replacement is inserted
+    /// literally; `$` has no special meaning.
+    fn replace_iter<'t>(
+        self,
+        text: &'t str,
+        replacement: &'t str,
+    ) -> ReplacePieces<'t, Self> where Self: RegularExpression<Text=str> {
+        ReplacePieces {
+            it: self.find_iter(text),
+            replacement: replacement,
+            last: 0,
+            pending: None,
+            finished: false,
+        }
+    }
+
+    /// Returns an iterator over the pieces of text between successive
+    /// non-overlapping matches.
+    ///
+    /// Pieces are yielded as byte ranges. Like `str::split`, a match at the
+    /// beginning or end of the text yields an empty piece there.
+    fn split_iter(
+        self,
+        text: &Self::Text,
+    ) -> SplitMatches<Self> {
+        SplitMatches { it: self.find_iter(text), last: 0, finished: false }
+    }
+
+    /// Returns an iterator over at most `limit` pieces of text between
+    /// successive non-overlapping matches.
+    ///
+    /// The final piece is whatever remains after `limit - 1` matches, which
+    /// may itself contain matches.
+    fn splitn_iter(
+        self,
+        text: &Self::Text,
+        limit: usize,
+    ) -> SplitNMatches<Self> {
+        SplitNMatches { splits: self.split_iter(text), n: limit }
+    }
+
+    /// Returns an iterator over the leftmost-first match at every position,
+    /// including matches that overlap.
+    ///
+    /// After a match at `(s, e)`, the next search starts at the character
+    /// following `s` rather than at `e`.
+    fn overlapping_iter(
+        self,
+        text: &Self::Text,
+    ) -> OverlappingMatches<Self> {
+        OverlappingMatches {
+            re: self,
+            text: text,
+            next_start: 0,
+        }
+    }
+
+    /// Returns an iterator over non-overlapping matches, starting with the
+    /// last match in the text and moving backwards.
+    fn find_iter_rev(
+        self,
+        text: &Self::Text,
+    ) -> RevMatches<Self> {
+        RevMatches {
+            re: self,
+            text: text,
+            pending: None,
+            started: false,
+        }
+    }
+
     /// Returns an iterator over all non-overlapping successive leftmost-first
     /// matches with captures.
     fn captures_iter(
         self,
         text: &Self::Text,
     ) -> CaptureMatches<Self> {
-        CaptureMatches(self.find_iter(text))
+        CaptureMatches { it: self.find_iter(text), peeked: None }
+    }
+
+    /// Finds all non-overlapping successive leftmost-first matches with
+    /// captures up front and returns an iterator over their capture slots.
+    ///
+    /// The matches are the same as those of `captures_iter`, and each item
+    /// holds `slots_len()` slots laid out as described on `Slot`. Like
+    /// `matches_collected`, the iterator knows its length and can be
+    /// reversed.
+    fn captures_collected(
+        self,
+        text: &Self::Text,
+    ) -> CollectedCaptures where Self::Text: AsRef<[u8]> {
+        let slots_len = self.slots_len();
+        let mut all = vec![];
+        let mut it = self.captures_iter_reuse(text);
+        while let Some(slots) = it.next_slots() {
+            all.push(slots.to_vec());
+        }
+        CollectedCaptures { it: all.into_iter(), slots_len: slots_len }
+    }
+
+    /// Like `captures_iter`, but reuses a single buffer of capture slots for
+    /// every match instead of allocating a new one.
+    fn captures_iter_reuse(
+        self,
+        text: &Self::Text,
+    ) -> CaptureMatchesReuse<Self> {
+        let locs = self.locations();
+        CaptureMatchesReuse { it: self.find_iter(text), locs: locs }
     }
 }
 
 /// An iterator over all non-overlapping successive leftmost-first matches.
+///
+/// Matches are yielded in order. The start of every match is greater than or
+/// equal to the end of the match before it, so no two matches overlap. An
+/// empty match is never yielded at the position where the previous match
+/// ended.
 pub struct Matches<'t, R> where R: RegularExpression, R::Text: 't {
     re: R,
     text: &'t R::Text,
@@ -195,74 +572,1363 @@ impl<'t, R> Iterator for Matches<'t, R>
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<(usize, usize)> {
-        if self.last_end > self.text.as_ref().len() {
+        next_match(
+            &self.re, self.text, &mut self.last_end, &mut self.last_match)
+    }
+
+    /// Every match except an empty one at the end of the text consumes at
+    /// least one position, so at most one match can start at each remaining
+    /// position.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.text.as_ref().len();
+        if self.last_end > len {
+            (0, Some(0))
+        } else {
+            (0, Some(len - self.last_end + 1))
+        }
+    }
+}
+
+/// Finds the next match of a scan over all non-overlapping successive
+/// leftmost-first matches.
+///
+/// `last_end` is where the next search starts and `last_match` is the end
+/// of the previous match, if any. Both are updated when a match is found.
+fn next_match<R>(
+    re: &R,
+    text: &R::Text,
+    last_end: &mut usize,
+    last_match: &mut Option<usize>,
+) -> Option<(usize, usize)>
+        where R: RegularExpression, R::Text: AsRef<[u8]> {
+    loop {
+        if *last_end > text.as_ref().len() {
             return None;
         }
-        let (s, e) = match self.re.find_at(self.text, self.last_end) {
+        let (s, e) = match re.find_at(text, *last_end) {
             None => return None,
             Some((s, e)) => (s, e),
         };
+        debug_assert!(
+            last_match.map_or(true, |prev| prev <= s),
+            "match ({}, {}) starts before previous match ending at {:?}",
+            s, e, last_match);
         if s == e {
             // This is an empty match. To ensure we make progress, start
             // the next search at the smallest possible starting position
             // of the next match following this one.
-            self.last_end = self.re.next_after_empty(self.text, e);
+            *last_end = re.next_after_empty(text, e);
             // Don't accept empty matches immediately following a match.
             // Just move on to the next match.
-            if Some(e) == self.last_match {
-                return self.next();
+            if Some(e) == *last_match {
+                continue;
             }
         } else {
-            self.last_end = e;
+            *last_end = e;
         }
-        self.last_match = Some(e);
+        *last_match = Some(e);
+        return Some((s, e));
+    }
+}
+
+/// An iterator over matches that were all found up front.
+pub struct CollectedMatches(vec::IntoIter<(usize, usize)>);
+
+impl Iterator for CollectedMatches {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for CollectedMatches {
+    fn next_back(&mut self) -> Option<(usize, usize)> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for CollectedMatches {}
+
+/// An iterator over the capture slots of matches that were all found up
+/// front.
+pub struct CollectedCaptures {
+    it: vec::IntoIter<Vec<Slot>>,
+    slots_len: usize,
+}
+
+impl CollectedCaptures {
+    /// Returns the number of slots in each item, which is twice the number
+    /// of capture groups (including the group for the entire match).
+    ///
+    /// This is known even if there are no matches.
+    pub fn slots_len(&self) -> usize {
+        self.slots_len
+    }
+}
+
+impl Iterator for CollectedCaptures {
+    type Item = Vec<Slot>;
+
+    fn next(&mut self) -> Option<Vec<Slot>> {
+        self.it.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for CollectedCaptures {
+    fn next_back(&mut self) -> Option<Vec<Slot>> {
+        self.it.next_back()
+    }
+}
+
+impl ExactSizeIterator for CollectedCaptures {}
+
+/// Returns the number of characters in `text` that start before the byte
+/// offset `byte`.
+///
+/// When `byte` is on a character boundary, this is the character offset
+/// corresponding to it. Offsets in the middle of a character or past the
+/// end of `text` don't panic, and count every character that starts before
+/// them.
+pub fn byte_to_char_offset(text: &str, byte: usize) -> usize {
+    text.char_indices().take_while(|&(i, _)| i < byte).count()
+}
+
+/// An iterator over matches whose offsets are counted in characters.
+///
+/// Offsets are converted incrementally, so iterating over all matches takes
+/// time proportional to the length of the text.
+pub struct CharMatches<'t, R> where R: RegularExpression<Text=str> {
+    it: Matches<'t, R>,
+    byte: usize,
+    chars: usize,
+}
+
+impl<'t, R> CharMatches<'t, R> where R: RegularExpression<Text=str> {
+    /// Converts a byte offset at or after the previous one.
+    fn to_chars(&mut self, byte: usize) -> usize {
+        let rest = &self.it.text()[self.byte..];
+        self.chars += byte_to_char_offset(rest, byte - self.byte);
+        self.byte = byte;
+        self.chars
+    }
+}
+
+impl<'t, R> Iterator for CharMatches<'t, R>
+        where R: RegularExpression<Text=str> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let (s, e) = match self.it.next() {
+            None => return None,
+            Some((s, e)) => (s, e),
+        };
+        let s = self.to_chars(s);
+        let e = self.to_chars(e);
         Some((s, e))
     }
 }
 
-/// An iterator over all non-overlapping successive leftmost-first matches with
-/// captures.
-pub struct CaptureMatches<'t, R>(Matches<'t, R>)
-    where R: RegularExpression, R::Text: 't;
+/// An iterator over the slices of text matched by a regular expression.
+pub struct StrMatches<'t, R>(Matches<'t, R>)
+    where R: RegularExpression<Text=str>;
 
-impl<'t, R> CaptureMatches<'t, R> where R: RegularExpression, R::Text: 't {
+impl<'t, R> Iterator for StrMatches<'t, R>
+        where R: RegularExpression<Text=str> {
+    type Item = &'t str;
+
+    fn next(&mut self) -> Option<&'t str> {
+        let text = self.0.text();
+        self.0.next().map(|(s, e)| &text[s..e])
+    }
+}
+
+/// An iterator over the pieces of a replace-all, as produced by
+/// `replace_iter`.
+pub struct ReplacePieces<'t, R> where R: RegularExpression<Text=str> {
+    it: Matches<'t, R>,
+    replacement: &'t str,
+    last: usize,
+    /// Set after yielding the text before a match, so that the replacement
+    /// comes next.
+    pending: Option<usize>,
+    finished: bool,
+}
+
+impl<'t, R> Iterator for ReplacePieces<'t, R>
+        where R: RegularExpression<Text=str> {
+    type Item = Cow<'t, str>;
+
+    fn next(&mut self) -> Option<Cow<'t, str>> {
+        if let Some(e) = self.pending.take() {
+            self.last = e;
+            return Some(Cow::Borrowed(self.replacement));
+        }
+        if self.finished {
+            return None;
+        }
+        let text = self.it.text();
+        match self.it.next() {
+            None => {
+                self.finished = true;
+                Some(Cow::Borrowed(&text[self.last..]))
+            }
+            Some((s, e)) => {
+                self.pending = Some(e);
+                Some(Cow::Borrowed(&text[self.last..s]))
+            }
+        }
+    }
+}
+
+/// An iterator over the byte ranges of text between successive matches.
+pub struct SplitMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    it: Matches<'t, R>,
+    last: usize,
+    finished: bool,
+}
+
+impl<'t, R> SplitMatches<'t, R> where R: RegularExpression, R::Text: 't {
     /// Return the text being searched.
     pub fn text(&self) -> &'t R::Text {
-        self.0.text()
+        self.it.text()
     }
 
     /// Return the underlying regex.
     pub fn regex(&self) -> &R {
-        self.0.regex()
+        self.it.regex()
     }
 }
 
-impl<'t, R> Iterator for CaptureMatches<'t, R>
+impl<'t, R> Iterator for SplitMatches<'t, R>
         where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
-    type Item = Locations;
+    type Item = (usize, usize);
 
-    fn next(&mut self) -> Option<Locations> {
-        if self.0.last_end > self.0.text.as_ref().len() {
-            return None
-        }
-        let mut locs = self.0.re.locations();
-        let (s, e) = match self.0.re.read_captures_at(
-            &mut locs,
-            self.0.text,
-            self.0.last_end,
-        ) {
-            None => return None,
-            Some((s, e)) => (s, e),
-        };
-        if s == e {
-            self.0.last_end = self.0.re.next_after_empty(self.0.text, e);
-            if Some(e) == self.0.last_match {
-                return self.next();
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.finished {
+            return None;
+        }
+        match self.it.next() {
+            None => {
+                self.finished = true;
+                Some((self.last, self.it.text.as_ref().len()))
             }
-        } else {
-            self.0.last_end = e;
+            Some((s, e)) => {
+                let piece = (self.last, s);
+                self.last = e;
+                Some(piece)
+            }
+        }
+    }
+}
+
+/// An iterator over at most `N` byte ranges of text between successive
+/// matches.
+///
+/// The last range covers whatever remains after splitting.
+pub struct SplitNMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    splits: SplitMatches<'t, R>,
+    n: usize,
+}
+
+impl<'t, R> Iterator for SplitNMatches<'t, R>
+        where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.n == 0 {
+            return None;
+        }
+        self.n -= 1;
+        if self.n > 0 {
+            return self.splits.next();
         }
-        self.0.last_match = Some(e);
-        Some(locs)
+        if self.splits.finished {
+            return None;
+        }
+        self.splits.finished = true;
+        Some((self.splits.last, self.splits.it.text.as_ref().len()))
+    }
+}
+
+/// An iterator over leftmost-first matches that may overlap.
+///
+/// Matches are yielded in order of their start, and every match starts at a
+/// different position. For example, `aa` matches `aaaa` at `(0, 2)`, `(1, 3)`
+/// and `(2, 4)`.
+pub struct OverlappingMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    re: R,
+    text: &'t R::Text,
+    next_start: usize,
+}
+
+impl<'t, R> OverlappingMatches<'t, R>
+        where R: RegularExpression, R::Text: 't {
+    /// Return the text being searched.
+    pub fn text(&self) -> &'t R::Text {
+        self.text
+    }
+
+    /// Return the underlying regex.
+    pub fn regex(&self) -> &R {
+        &self.re
+    }
+}
+
+impl<'t, R> Iterator for OverlappingMatches<'t, R>
+        where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.next_start > self.text.as_ref().len() {
+            return None;
+        }
+        let (s, e) = match self.re.find_at(self.text, self.next_start) {
+            None => return None,
+            Some((s, e)) => (s, e),
+        };
+        // Every search starts after the start of the previous match, so the
+        // same match is never reported twice, even when it is empty.
+        self.next_start = self.re.next_after_empty(self.text, s);
+        Some((s, e))
+    }
+}
+
+/// Like `find_before`, but only considers matches starting at or before
+/// `start`.
+/// An iterator over non-overlapping matches, from the end of the text to the
+/// beginning.
+///
+/// Each match is found with `find_before_at`, bounded by the start of the
+/// match yielded before it, so no two matches overlap. Like `Matches`, an
+/// empty match is never yielded at the position where the following match
+/// ends.
+///
+/// When no two candidate matches overlap, the matches are exactly those of
+/// `Matches` in reverse order. Otherwise the two can differ, since which of
+/// several overlapping candidates wins depends on the direction of the scan.
+/// For example, `aa` matches `aaaa` at `(0, 2)` and `(2, 4)` in both
+/// directions, but matches `aaa` at `(0, 2)` forwards and at `(1, 3)`
+/// backwards.
+pub struct RevMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    re: R,
+    text: &'t R::Text,
+    pending: Option<(usize, usize)>,
+    started: bool,
+}
+
+impl<'t, R> RevMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    /// Return the text being searched.
+    pub fn text(&self) -> &'t R::Text {
+        self.text
+    }
+
+    /// Return the underlying regex.
+    pub fn regex(&self) -> &R {
+        &self.re
+    }
+
+    /// Finds the match preceding a match that starts at `s`. An empty match
+    /// at `s` itself is never reported.
+    fn before(&self, s: usize) -> Option<(usize, usize)> {
+        if s == 0 {
+            return None;
+        }
+        let start = self.re.prev_before_empty(self.text, s);
+        self.re.find_before_at(self.text, start, s)
+    }
+}
+
+impl<'t, R> Iterator for RevMatches<'t, R>
+        where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let (s, e) = match self.pending.take() {
+            Some(m) => m,
+            None if self.started => return None,
+            None => {
+                self.started = true;
+                let len = self.text.as_ref().len();
+                match self.re.find_before(self.text, len) {
+                    None => return None,
+                    Some(m) => m,
+                }
+            }
+        };
+        self.pending = self.before(s);
+        if s == e {
+            // The forward iterator skips an empty match immediately
+            // following another match. Do the same by looking at the match
+            // before this one.
+            if let Some((ps, pe)) = self.pending {
+                if pe == s {
+                    self.pending = self.before(ps);
+                    return Some((ps, pe));
+                }
+            }
+        }
+        Some((s, e))
+    }
+}
+
+/// A lexer that splits a haystack into tokens, each matched by one of several
+/// regular expressions.
+///
+/// Starting at the beginning of the text, every regex is tried with
+/// `match_anchored_at` at the current position. By default the first regex
+/// that matches wins, which makes the order of the regexes significant.
+/// With `longest`, the regex with the longest match wins instead, and ties
+/// go to the earlier regex. Empty matches are ignored, since they would not
+/// move the position forward.
+///
+/// Each token is yielded as `Ok((index, start, end))`, where `index` is the
+/// position of the winning regex in the slice. If no regex matches at the
+/// current position, `Err(position)` is yielded and iteration stops.
+///
+/// Since `match_anchored_at` is an anchored search for the engines in this
+/// crate, a regex that doesn't match at the current position gives up as soon
+/// as no match is possible there, instead of looking through the rest of the
+/// text. For token regexes that fail quickly, tokenizing takes time linear in
+/// the length of the text.
+pub struct Tokenizer<'r, 't, R> where R: RegularExpression + 'r, R::Text: 't {
+    res: &'r [R],
+    text: &'t R::Text,
+    pos: usize,
+    longest: bool,
+    done: bool,
+}
+
+impl<'r, 't, R> Tokenizer<'r, 't, R>
+        where R: RegularExpression + 'r, R::Text: 't {
+    /// Create a new tokenizer for `text` using the token regexes given.
+    pub fn new(res: &'r [R], text: &'t R::Text) -> Self {
+        Tokenizer {
+            res: res,
+            text: text,
+            pos: 0,
+            longest: false,
+            done: false,
+        }
+    }
+
+    /// When enabled, the longest match at each position wins instead of the
+    /// first.
+    pub fn longest(mut self, yes: bool) -> Self {
+        self.longest = yes;
+        self
+    }
+
+    /// Return the text being tokenized.
+    pub fn text(&self) -> &'t R::Text {
+        self.text
+    }
+}
+
+impl<'r, 't, R> Iterator for Tokenizer<'r, 't, R>
+        where R: RegularExpression + 'r, R::Text: 't + AsRef<[u8]> {
+    type Item = Result<(usize, usize, usize), usize>;
+
+    fn next(&mut self) -> Option<Result<(usize, usize, usize), usize>> {
+        if self.done || self.pos >= self.text.as_ref().len() {
+            return None;
+        }
+        let mut best: Option<(usize, usize)> = None;
+        for (i, re) in self.res.iter().enumerate() {
+            let end = match re.match_anchored_at(self.text, self.pos) {
+                Some(end) if end > self.pos => end,
+                _ => continue,
+            };
+            if best.map_or(true, |(_, best_end)| end > best_end) {
+                best = Some((i, end));
+            }
+            if !self.longest {
+                break;
+            }
+        }
+        match best {
+            None => {
+                self.done = true;
+                Some(Err(self.pos))
+            }
+            Some((i, end)) => {
+                let start = self.pos;
+                self.pos = end;
+                Some(Ok((i, start, end)))
+            }
+        }
+    }
+}
+
+/// An iterator over all non-overlapping successive leftmost-first matches with
+/// captures.
+pub struct CaptureMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    it: Matches<'t, R>,
+    peeked: Option<Option<(usize, usize)>>,
+}
+
+impl<'t, R> CaptureMatches<'t, R> where R: RegularExpression, R::Text: 't {
+    /// Return the text being searched.
+    pub fn text(&self) -> &'t R::Text {
+        self.it.text()
+    }
+
+    /// Return the underlying regex.
+    pub fn regex(&self) -> &R {
+        self.it.regex()
+    }
+}
+
+impl<'t, R> CaptureMatches<'t, R>
+        where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
+    /// Return the start of the next match without computing its captures.
+    ///
+    /// This only runs `find_at`, which is typically much cheaper than
+    /// `read_captures_at`. The match found is cached, and the subsequent call
+    /// to `next` computes captures for exactly that match by searching from
+    /// its start. This relies on the capture search agreeing with `find_at`
+    /// on the overall bounds of the match, which every matching engine
+    /// guarantees.
+    pub fn peek_start(&mut self) -> Option<usize> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.it.next());
+        }
+        self.peeked.unwrap().map(|(s, _)| s)
+    }
+}
+
+impl<'t, R> Iterator for CaptureMatches<'t, R>
+        where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
+    type Item = Locations;
+
+    fn next(&mut self) -> Option<Locations> {
+        if let Some(peeked) = self.peeked.take() {
+            let (s, e) = match peeked {
+                None => return None,
+                Some((s, e)) => (s, e),
+            };
+            let mut locs = self.it.re.locations();
+            let m = self.it.re.read_captures_at(&mut locs, self.it.text, s);
+            debug_assert_eq!(m, Some((s, e)));
+            return Some(locs);
+        }
+        loop {
+            if self.it.last_end > self.it.text.as_ref().len() {
+                return None
+            }
+            let mut locs = self.it.re.locations();
+            let (s, e) = match self.it.re.read_captures_at(
+                &mut locs,
+                self.it.text,
+                self.it.last_end,
+            ) {
+                None => return None,
+                Some((s, e)) => (s, e),
+            };
+            if s == e {
+                self.it.last_end =
+                    self.it.re.next_after_empty(self.it.text, e);
+                if Some(e) == self.it.last_match {
+                    continue;
+                }
+            } else {
+                self.it.last_end = e;
+            }
+            self.it.last_match = Some(e);
+            return Some(locs);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.peeked {
+            None => self.it.size_hint(),
+            Some(None) => (0, Some(0)),
+            Some(Some(_)) => {
+                let (_, upper) = self.it.size_hint();
+                (1, upper.map(|n| n + 1))
+            }
+        }
+    }
+}
+
+/// A lending iterator over all non-overlapping successive leftmost-first
+/// matches with captures.
+///
+/// This can't implement `Iterator`, since the slots returned by `next_slots`
+/// borrow from the iterator itself. Each slice is only valid until the next
+/// call to `next_slots`, which overwrites it, so it is used with `while let`
+/// rather than a `for` loop. Slots that need to outlive the call must be
+/// copied out.
+pub struct CaptureMatchesReuse<'t, R> where R: RegularExpression, R::Text: 't {
+    it: Matches<'t, R>,
+    locs: Locations,
+}
+
+impl<'t, R> CaptureMatchesReuse<'t, R>
+        where R: RegularExpression, R::Text: 't {
+    /// Return the text being searched.
+    pub fn text(&self) -> &'t R::Text {
+        self.it.text()
+    }
+
+    /// Return the underlying regex.
+    pub fn regex(&self) -> &R {
+        self.it.regex()
+    }
+}
+
+impl<'t, R> CaptureMatchesReuse<'t, R>
+        where R: RegularExpression, R::Text: 't + AsRef<[u8]> {
+    /// Return the capture slots of the next match, laid out as described on
+    /// `Slot`.
+    pub fn next_slots(&mut self) -> Option<&[Slot]> {
+        loop {
+            if self.it.last_end > self.it.text.as_ref().len() {
+                return None;
+            }
+            for slot in as_slots(&mut self.locs).iter_mut() {
+                *slot = None;
+            }
+            let (s, e) = match self.it.re.read_captures_at(
+                &mut self.locs,
+                self.it.text,
+                self.it.last_end,
+            ) {
+                None => return None,
+                Some((s, e)) => (s, e),
+            };
+            if s == e {
+                self.it.last_end =
+                    self.it.re.next_after_empty(self.it.text, e);
+                if Some(e) == self.it.last_match {
+                    continue;
+                }
+            } else {
+                self.it.last_end = e;
+            }
+            self.it.last_match = Some(e);
+            return Some(&self.locs.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::repeat;
+
+    use exec::{ExecBuilder, ExecNoSyncStr};
+    use super::{
+        CaptureGroup, Locations, RegularExpression, Slot, Tokenizer, as_slots,
+        slots_to_groups,
+    };
+
+    fn forward_and_reverse(
+        re: &str,
+        text: &str,
+    ) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let forward = exec.searcher_str().find_iter(text).collect();
+        let mut reverse: Vec<_> =
+            exec.searcher_str().find_iter_rev(text).collect();
+        reverse.reverse();
+        (forward, reverse)
+    }
+
+    fn assert_mirror(re: &str, text: &str) {
+        let (forward, reverse) = forward_and_reverse(re, text);
+        assert_eq!(forward, reverse, "pattern {:?} on {:?}", re, text);
+    }
+
+    #[test]
+    fn find_iter_rev_mirrors_forward() {
+        assert_mirror(r"[0-9]+", "a1b22c333");
+        assert_mirror(r"\bfoo\b", "foo foobar foo");
+        assert_mirror(r"x", "abc");
+        assert_mirror(r"δ", "aδbδδ");
+        assert_mirror(r"^a|b$", "ab");
+    }
+
+    #[test]
+    fn find_iter_rev_empty_matches() {
+        assert_mirror(r"", "");
+        assert_mirror(r"", "abc");
+        assert_mirror(r"", "δ☃");
+        assert_mirror(r"a*", "ab");
+        assert_mirror(r"a*", "ba");
+        assert_mirror(r"a*", "baab");
+    }
+
+    #[test]
+    fn find_iter_rev_overlapping_candidates() {
+        let (forward, reverse) = forward_and_reverse(r"aa", "aaa");
+        assert_eq!(forward, vec![(0, 2)]);
+        assert_eq!(reverse, vec![(1, 3)]);
+    }
+
+    fn split(re: &str, text: &str) -> Vec<(usize, usize)> {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let pieces = exec.searcher_str().split_iter(text).collect();
+        pieces
+    }
+
+    fn splitn(re: &str, text: &str, limit: usize) -> Vec<(usize, usize)> {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let pieces = exec.searcher_str().splitn_iter(text, limit).collect();
+        pieces
+    }
+
+    /// Splits `text` on ASCII digits with the standard library, for
+    /// comparison.
+    fn std_split(text: &str) -> Vec<(usize, usize)> {
+        let base = text.as_ptr() as usize;
+        text.split(|c: char| c.is_digit(10)).map(|piece| {
+            let start = piece.as_ptr() as usize - base;
+            (start, start + piece.len())
+        }).collect()
+    }
+
+    #[test]
+    fn split_iter() {
+        assert_eq!(split(r"\d", "a1b2c"), vec![(0, 1), (2, 3), (4, 5)]);
+        for text in &["a1b2c", "1a2", "12", "", "abc", "1"] {
+            assert_eq!(split(r"[0-9]", text), std_split(text), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn split_iter_empty_matches() {
+        assert_eq!(split(r"", "ab"), vec![(0, 0), (0, 1), (1, 2), (2, 2)]);
+        assert_eq!(split(r"x*", "axb"), vec![(0, 0), (0, 1), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn splitn_iter() {
+        assert_eq!(splitn(r"\d", "a1b2c", 0), vec![]);
+        assert_eq!(splitn(r"\d", "a1b2c", 1), vec![(0, 5)]);
+        assert_eq!(splitn(r"\d", "a1b2c", 2), vec![(0, 1), (2, 5)]);
+        assert_eq!(splitn(r"\d", "a1b2c", 3), vec![(0, 1), (2, 3), (4, 5)]);
+        assert_eq!(splitn(r"\d", "a1b2c", 4), vec![(0, 1), (2, 3), (4, 5)]);
+        assert_eq!(splitn(r"\d", "a1", 2), vec![(0, 1), (2, 2)]);
+        assert_eq!(splitn(r"\d", "", 2), vec![(0, 0)]);
+    }
+
+    fn overlapping(re: &str, text: &str) -> Vec<(usize, usize)> {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let matches = exec.searcher_str().overlapping_iter(text).collect();
+        matches
+    }
+
+    #[test]
+    fn overlapping_iter() {
+        assert_eq!(overlapping(r"aa", "aaaa"), vec![(0, 2), (1, 3), (2, 4)]);
+        assert_eq!(overlapping(r"aa", "aaxaa"), vec![(0, 2), (3, 5)]);
+        assert_eq!(overlapping(r"[0-9]{2}", "δ123"),
+                   vec![(2, 4), (3, 5)]);
+        assert_eq!(overlapping(r"x", "aaa"), vec![]);
+    }
+
+    #[test]
+    fn overlapping_iter_empty_matches() {
+        assert_eq!(overlapping(r"a*", "aaa"),
+                   vec![(0, 3), (1, 3), (2, 3), (3, 3)]);
+        assert_eq!(overlapping(r"", "δa"), vec![(0, 0), (2, 2), (3, 3)]);
+        assert_eq!(overlapping(r"", ""), vec![(0, 0)]);
+    }
+
+    fn find_in(re: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let m = exec.searcher_str().find_in("abc abc", start, end);
+        m
+    }
+
+    #[test]
+    fn find_in_rejects_match_past_end() {
+        assert_eq!(find_in(r"abc", 0, 7), Some((0, 3)));
+        assert_eq!(find_in(r"abc", 1, 7), Some((4, 7)));
+        assert_eq!(find_in(r"abc", 1, 6), None);
+        assert_eq!(find_in(r"[a-c]+", 1, 6), Some((1, 3)));
+        assert_eq!(find_in(r"[a-c]+", 4, 6), Some((4, 6)));
+    }
+
+    #[test]
+    fn find_in_anchors() {
+        assert_eq!(find_in(r"^bc", 0, 7), None);
+        assert_eq!(find_in(r"^bc", 1, 7), Some((1, 3)));
+        assert_eq!(find_in(r"ab$", 0, 7), None);
+        assert_eq!(find_in(r"ab$", 0, 6), Some((4, 6)));
+        assert_eq!(find_in(r"\bb", 1, 7), Some((1, 2)));
+        assert_eq!(find_in(r"^$", 3, 3), Some((3, 3)));
+    }
+
+    #[test]
+    fn find_in_bytes() {
+        let exec = ExecBuilder::new(r"a$").bytes(true).build().unwrap();
+        assert_eq!(exec.searcher().find_in(b"aab", 0, 3), None);
+        assert_eq!(exec.searcher().find_in(b"aab", 0, 2), Some((1, 2)));
+    }
+
+    #[test]
+    fn find_in_char_boundaries() {
+        let exec = ExecBuilder::new(r"\w+").build().unwrap();
+        assert_eq!(exec.searcher_str().find_in("aé", 0, 3), Some((0, 3)));
+        assert_eq!(a_or_ab_plugin().find_in("éa", 2, 3), Some((2, 3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "0..2 is not a range of character boundaries")]
+    fn find_in_end_inside_char() {
+        let exec = ExecBuilder::new(r"\w+").build().unwrap();
+        exec.searcher_str().find_in("aé", 0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "1..3 is not a range of character boundaries")]
+    fn find_in_plugin_start_inside_char() {
+        a_or_ab_plugin().find_in("éa", 1, 3);
+    }
+
+    #[test]
+    fn matches_collected() {
+        let exec = ExecBuilder::new(r"a*").build().unwrap();
+        let text = "baab";
+        let forward: Vec<_> = exec.searcher_str().find_iter(text).collect();
+        let mut it = exec.searcher_str().matches_collected(text);
+        assert_eq!(it.len(), forward.len());
+        it.next();
+        assert_eq!(it.len(), forward.len() - 1);
+
+        let all = exec.searcher_str().matches_collected(text);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all.collect::<Vec<_>>(), forward);
+        let mut reverse: Vec<_> =
+            exec.searcher_str().matches_collected(text).rev().collect();
+        reverse.reverse();
+        assert_eq!(reverse, forward);
+    }
+
+    #[test]
+    fn byte_to_char_offset() {
+        let text = "aé☃b";
+        let offsets: Vec<_> = (0..text.len() + 2)
+            .map(|i| super::byte_to_char_offset(text, i))
+            .collect();
+        assert_eq!(offsets, vec![0, 1, 2, 2, 3, 3, 3, 4, 4]);
+    }
+
+    #[test]
+    fn find_iter_chars() {
+        let exec = ExecBuilder::new(r"[é☃]+|b").build().unwrap();
+        let text = "aé☃bé";
+        let chars: Vec<_> =
+            exec.searcher_str().find_iter_chars(text).collect();
+        assert_eq!(chars, vec![(1, 3), (3, 4), (4, 5)]);
+        let bytes: Vec<_> = exec.searcher_str().find_iter(text).collect();
+        assert_eq!(bytes, vec![(1, 6), (6, 7), (7, 9)]);
+    }
+
+    #[test]
+    fn find_iter_str() {
+        let exec = ExecBuilder::new(r"\d").build().unwrap();
+        let strs: Vec<_> =
+            exec.searcher_str().find_iter_str("a1b2").collect();
+        assert_eq!(strs, vec!["1", "2"]);
+
+        let exec = ExecBuilder::new(r"b*").build().unwrap();
+        let strs: Vec<_> =
+            exec.searcher_str().find_iter_str("abé").collect();
+        assert_eq!(strs, vec!["", "b", ""]);
+    }
+
+    fn replace(re: &str, text: &str, replacement: &str) -> String {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let pieces = exec.searcher_str().replace_iter(text, replacement);
+        pieces.collect()
+    }
+
+    #[test]
+    fn replace_iter() {
+        assert_eq!(replace(r"\d", "a1b2c", "#"), "a#b#c");
+        assert_eq!(replace(r"\d+", "12ab345", "<$0>"), "<$0>ab<$0>");
+        assert_eq!(replace(r"x", "abc", "y"), "abc");
+        assert_eq!(replace(r"é", "aéb", "e"), "aeb");
+        assert_eq!(replace(r"\d", "", "#"), "");
+
+        let exec = ExecBuilder::new(r"\d").build().unwrap();
+        let pieces: Vec<_> =
+            exec.searcher_str().replace_iter("1a2", "-").collect();
+        assert_eq!(pieces, vec!["", "-", "a", "-", ""]);
+    }
+
+    #[test]
+    fn replace_iter_empty_matches() {
+        assert_eq!(replace(r"", "ab", "-"), "-a-b-");
+        assert_eq!(replace(r"x*", "axxb", "-"), "-a-b-");
+        assert_eq!(replace(r"", "", "-"), "-");
+    }
+
+    #[test]
+    fn nth_and_last_match() {
+        let exec = ExecBuilder::new(r"\d").build().unwrap();
+        let re = exec.searcher_str();
+        assert_eq!(re.nth_match("a1b2c3", 0), Some((1, 2)));
+        assert_eq!(re.nth_match("a1b2c3", 1), Some((3, 4)));
+        assert_eq!(re.nth_match("a1b2c3", 3), None);
+        assert_eq!(re.last_match("a1b2c3"), Some((5, 6)));
+        assert_eq!(re.last_match("abc"), None);
+    }
+
+    #[test]
+    fn nth_and_last_match_empty() {
+        let exec = ExecBuilder::new(r"a*").build().unwrap();
+        let re = exec.searcher_str();
+        let all: Vec<_> = exec.searcher_str().find_iter("baab").collect();
+        for (i, &m) in all.iter().enumerate() {
+            assert_eq!(re.nth_match("baab", i), Some(m));
+        }
+        assert_eq!(re.nth_match("baab", all.len()), None);
+        assert_eq!(re.last_match("baab"), all.last().cloned());
+    }
+
+    #[test]
+    fn match_count() {
+        let tests = [
+            (r"\d", "a1b2c3"), (r"a*", "baab"), (r"", "δa"), (r"x", "abc"),
+            (r"\b", "ab cd"), (r"[a-z]+", "ab cd ef"),
+        ];
+        for &(re, text) in &tests {
+            let exec = ExecBuilder::new(re).build().unwrap();
+            assert_eq!(exec.searcher_str().match_count(text),
+                       exec.searcher_str().find_iter(text).count(),
+                       "{:?} on {:?}", re, text);
+        }
+    }
+
+    #[test]
+    fn match_anchored_at() {
+        let exec = ExecBuilder::new(r"[0-9]+").build().unwrap();
+        let re = exec.searcher_str();
+        assert_eq!(re.match_anchored_at("ab12", 0), None);
+        assert_eq!(re.find_at("ab12", 0), Some((2, 4)));
+        assert!(re.is_match_at("ab12", 0));
+        assert_eq!(re.match_anchored_at("ab12", 2), Some(4));
+        assert_eq!(re.match_anchored_at("ab12", 3), Some(4));
+        assert_eq!(re.match_anchored_at("ab12", 4), None);
+
+        let exec = ExecBuilder::new(r"a*").build().unwrap();
+        assert_eq!(exec.searcher_str().match_anchored_at("ba", 0), Some(0));
+    }
+
+    #[test]
+    fn match_anchored_at_agrees_with_find_at() {
+        let res = &[
+            r"[0-9]+", r"a|ab", r"\ba", r"a$", r"foo", r"(?m)^b",
+            r"x*", r"\w+?", r"(?m)b$",
+        ];
+        for re in res {
+            let exec = ExecBuilder::new(re).build().unwrap();
+            for text in &["ab12", "a ab foo", "aab\nb", "foofoo", ""] {
+                for i in 0..text.len() + 1 {
+                    if !text.is_char_boundary(i) {
+                        continue;
+                    }
+                    let expected = match exec.searcher().find_at(
+                        text.as_bytes(), i) {
+                        Some((s, e)) if s == i => Some(e),
+                        _ => None,
+                    };
+                    let got = exec.searcher().match_anchored_at(
+                        text.as_bytes(), i);
+                    assert_eq!(got, expected, "{:?} {:?} {}", re, text, i);
+                }
+            }
+        }
+    }
+
+    fn tokenize(
+        text: &str,
+        longest: bool,
+    ) -> Vec<Result<(usize, usize, usize), usize>> {
+        let execs: Vec<_> = [r"[0-9]+", r"[-+*/]", r"\*\*", r" *"].iter()
+            .map(|re| ExecBuilder::new(re).build().unwrap())
+            .collect();
+        let res: Vec<_> = execs.iter().map(|e| e.searcher_str()).collect();
+        let tokens = Tokenizer::new(&res, text).longest(longest).collect();
+        tokens
+    }
+
+    #[test]
+    fn tokenizer() {
+        assert_eq!(tokenize("12+34", false), vec![
+            Ok((0, 0, 2)), Ok((1, 2, 3)), Ok((0, 3, 5)),
+        ]);
+        assert_eq!(tokenize("", false), vec![]);
+    }
+
+    #[test]
+    fn tokenizer_first_or_longest() {
+        assert_eq!(tokenize("2 ** 3", false), vec![
+            Ok((0, 0, 1)), Ok((3, 1, 2)), Ok((1, 2, 3)), Ok((1, 3, 4)),
+            Ok((3, 4, 5)), Ok((0, 5, 6)),
+        ]);
+        assert_eq!(tokenize("2 ** 3", true), vec![
+            Ok((0, 0, 1)), Ok((3, 1, 2)), Ok((2, 2, 4)), Ok((3, 4, 5)),
+            Ok((0, 5, 6)),
+        ]);
+    }
+
+    #[test]
+    fn tokenizer_error() {
+        assert_eq!(tokenize("1+x+2", false), vec![
+            Ok((0, 0, 1)), Ok((1, 1, 2)), Err(2),
+        ]);
+    }
+
+    #[test]
+    fn tokenizer_unmatched_pattern_is_cheap() {
+        // `zz` never matches, so every attempt with it fails. Each of those
+        // must give up right away rather than scan the rest of the text,
+        // otherwise this takes time quadratic in the length of the text.
+        let execs: Vec<_> = [r"[-+]", r"[0-9]+", r"zz"].iter()
+            .map(|re| ExecBuilder::new(re).build().unwrap())
+            .collect();
+        let res: Vec<_> = execs.iter().map(|e| e.searcher_str()).collect();
+        let text = repeat("1+").take(100_000).collect::<String>();
+        let tokens: Vec<_> =
+            Tokenizer::new(&res, &text).longest(true).collect();
+        assert_eq!(tokens.len(), 200_000);
+        assert!(tokens.iter().all(|t| t.is_ok()));
+    }
+
+    #[test]
+    fn advance_empty() {
+        use super::advance_empty;
+
+        let text = "a☃b".as_bytes();
+        assert_eq!(advance_empty(text, 0), 1);
+        assert_eq!(advance_empty(text, 1), 4);
+        assert_eq!(advance_empty(text, 4), 5);
+        assert_eq!(advance_empty(text, 5), 6);
+        // Not the start of a valid code point.
+        assert_eq!(advance_empty(text, 2), 3);
+        assert_eq!(advance_empty(b"\xFF\xE2\x98", 0), 1);
+        assert_eq!(advance_empty(b"\xFF\xE2\x98", 1), 2);
+    }
+
+    #[test]
+    fn find_before() {
+        let exec = ExecBuilder::new(r"[a-z]+").build().unwrap();
+        let re = exec.searcher_str();
+        assert_eq!(re.find_before("ab cd", 5), Some((3, 5)));
+        // `cd` is the match starting at 3, and it ends after 4.
+        assert_eq!(re.find_before("ab cd", 4), Some((0, 2)));
+        assert_eq!(re.find_before("ab cd", 3), Some((0, 2)));
+        assert_eq!(re.find_before("ab cd", 0), None);
+    }
+
+    /// Runs the trait's default `find_before_at` on top of an engine's own
+    /// `match_anchored_at`.
+    struct DefaultFindBefore<'c>(ExecNoSyncStr<'c>);
+
+    impl<'c> RegularExpression for DefaultFindBefore<'c> {
+        type Text = str;
+
+        fn slots_len(&self) -> usize { self.0.slots_len() }
+
+        fn next_after_empty(&self, text: &str, i: usize) -> usize {
+            self.0.next_after_empty(text, i)
+        }
+
+        fn prev_before_empty(&self, text: &str, i: usize) -> usize {
+            self.0.prev_before_empty(text, i)
+        }
+
+        fn shortest_match_at(
+            &self,
+            text: &str,
+            start: usize,
+        ) -> Option<usize> {
+            self.0.shortest_match_at(text, start)
+        }
+
+        fn is_match_at(&self, text: &str, start: usize) -> bool {
+            self.0.is_match_at(text, start)
+        }
+
+        fn find_at(&self, text: &str, start: usize) -> Option<(usize, usize)> {
+            self.0.find_at(text, start)
+        }
+
+        fn find_in(
+            &self,
+            text: &str,
+            start: usize,
+            end: usize,
+        ) -> Option<(usize, usize)> {
+            self.0.find_in(text, start, end)
+        }
+
+        fn match_anchored_at(
+            &self,
+            text: &str,
+            start: usize,
+        ) -> Option<usize> {
+            self.0.match_anchored_at(text, start)
+        }
+
+        fn read_captures_at(
+            &self,
+            locs: &mut Locations,
+            text: &str,
+            start: usize,
+        ) -> Option<(usize, usize)> {
+            self.0.read_captures_at(locs, text, start)
+        }
+    }
+
+    #[test]
+    fn find_before_at_agrees_with_default() {
+        let res = &[
+            r"a+", r"a+b", r"aa", r"a|ab|b", r"\w+", r"\bfoo\b", r"x*",
+            r"(?:ab)+", r"a+?", r"b$",
+        ];
+        for re in res {
+            let exec = ExecBuilder::new(re).build().unwrap();
+            let default = DefaultFindBefore(exec.searcher_str());
+            let texts = &["aaab", "abab aab", "foo afoo", "aaaa", "δaδ", ""];
+            for text in texts {
+                for end in 0..text.len() + 1 {
+                    if !text.is_char_boundary(end) {
+                        continue;
+                    }
+                    for start in 0..end + 1 {
+                        if !text.is_char_boundary(start) {
+                            continue;
+                        }
+                        assert_eq!(
+                            exec.searcher_str().find_before_at(
+                                text, start, end),
+                            default.find_before_at(text, start, end),
+                            "{:?} {:?} {}..{}", re, text, start, end);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn find_iter_rev_long_text() {
+        // Both of these take time quadratic in the length of the text unless
+        // each anchored search stops as soon as it can.
+        let run: String = repeat("a").take(200_000).collect();
+        let exec = ExecBuilder::new(r"x").build().unwrap();
+        let text = format!("x{}", run);
+        let matches: Vec<_> =
+            exec.searcher_str().find_iter_rev(&text).collect();
+        assert_eq!(matches, vec![(0, 1)]);
+
+        let exec = ExecBuilder::new(r"a+").build().unwrap();
+        let text = format!("b{}", run);
+        let matches: Vec<_> =
+            exec.searcher_str().find_iter_rev(&text).collect();
+        assert_eq!(matches, vec![(1, 200_001)]);
+    }
+
+    #[test]
+    fn find_iter_rev_bytes() {
+        let exec = ExecBuilder::new(r"(?-u:.)")
+            .bytes(true).only_utf8(false).build().unwrap();
+        let matches: Vec<_> =
+            exec.searcher().find_iter_rev(&b"a\xFFb"[..]).collect();
+        assert_eq!(matches, vec![(2, 3), (1, 2), (0, 1)]);
+    }
+
+    fn find_longest(re: &str, text: &str) -> Option<(usize, usize)> {
+        let exec = ExecBuilder::new(re).build().unwrap();
+        let longest = exec.searcher_str().find_longest_at(text, 0);
+        assert_eq!(longest.map(|(s, _)| s),
+                   exec.searcher_str().find_at(text, 0).map(|(s, _)| s));
+        longest
+    }
+
+    #[test]
+    fn find_longest_alternation() {
+        assert_eq!(find_longest(r"a|ab", "ab"), Some((0, 2)));
+        assert_eq!(find_longest(r"a|ab|abc", "xabcd"), Some((1, 4)));
+        assert_eq!(find_longest(r"ab|a", "ab"), Some((0, 2)));
+    }
+
+    #[test]
+    fn find_longest_lazy() {
+        assert_eq!(find_longest(r"a+?", "aaa"), Some((0, 3)));
+        assert_eq!(find_longest(r"(?:a|b)*?c?", "abc"), Some((0, 3)));
+    }
+
+    #[test]
+    fn find_longest_leftmost() {
+        // The leftmost match wins even if a later one would be longer.
+        assert_eq!(find_longest(r"b|abcd|bcdef", "abcdef"), Some((0, 4)));
+        assert_eq!(find_longest(r"cd|abcdef", "abcdx"), Some((2, 4)));
+    }
+
+    #[test]
+    fn find_longest_anchors() {
+        assert_eq!(find_longest(r"a\b|ab", "ab"), Some((0, 2)));
+        assert_eq!(find_longest(r"a$|a", "ab"), Some((0, 1)));
+        assert_eq!(find_longest(r"^b|bc", "abc"), Some((1, 3)));
+    }
+
+    #[test]
+    fn find_longest_none() {
+        assert_eq!(find_longest(r"a|ab", "xyz"), None);
+    }
+
+    #[test]
+    fn find_longest_bytes() {
+        let exec = ExecBuilder::new(r"a|ab").bytes(true).build().unwrap();
+        assert_eq!(exec.searcher().find_longest_at(b"xab", 0), Some((1, 3)));
+    }
+
+    /// A plugin with a leftmost-first matcher for `a|ab`, standing in for
+    /// the code the compiler plugin would generate.
+    fn a_or_ab_plugin() -> ::re_plugin::Plugin {
+        static NAMES: &'static [Option<&'static str>] = &[None];
+        static GROUPS: &'static [(&'static str, usize)] = &[];
+
+        fn prog(slots: &mut [Slot], text: &str, start: usize) -> bool {
+            match text[start..].find('a') {
+                None => false,
+                Some(i) => {
+                    if slots.len() >= 2 {
+                        slots[0] = Some(start + i);
+                        slots[1] = Some(start + i + 1);
+                    }
+                    true
+                }
+            }
+        }
+
+        ::re_plugin::Plugin {
+            original: "a|ab",
+            names: &NAMES,
+            groups: &GROUPS,
+            prog: prog,
+        }
+    }
+
+    #[test]
+    fn find_longest_plugin() {
+        let plugin = a_or_ab_plugin();
+        assert_eq!(plugin.find_at("xab", 0), Some((1, 2)));
+        // The plugin falls back to the default, which can't see past the
+        // preferred alternative.
+        assert_eq!(plugin.find_longest_at("xab", 0), Some((1, 2)));
+        assert_eq!(plugin.find_longest_at("xab", 2), None);
+    }
+
+    #[test]
+    fn find_longest_unicode() {
+        assert_eq!(find_longest(r"\w|\w+", "δδδ!"), Some((0, 6)));
+    }
+
+    #[test]
+    fn find_iter_many_empty_matches() {
+        // Every other empty match directly follows a non-empty one and is
+        // skipped, which used to cost a recursive call to `next`.
+        let text: String = repeat("ab").take(100_000).collect();
+        let tests = [(r"", 200_001), (r"a*", 100_001), (r"(a*)", 100_001)];
+        for &(re, expected) in &tests {
+            let exec = ExecBuilder::new(re).build().unwrap();
+            let count = exec.searcher_str().find_iter(&text).count();
+            assert_eq!(count, expected, "{}", re);
+            let count = exec.searcher_str().captures_iter(&text).count();
+            assert_eq!(count, expected, "{}", re);
+        }
+    }
+
+    #[test]
+    fn size_hint_bounds_matches() {
+        let tests = [
+            (r"", ""), (r"", "δa"), (r"a*", "baab"), (r"[0-9]", "a1b2c3"),
+            (r"x", "abc"), (r"a", "aaa"),
+        ];
+        for &(re, text) in &tests {
+            let exec = ExecBuilder::new(re).build().unwrap();
+            let total = exec.searcher_str().find_iter(text).count();
+            for skip in 0..total + 1 {
+                let mut it = exec.searcher_str().find_iter(text);
+                for _ in 0..skip {
+                    it.next();
+                }
+                let (_, upper) = it.size_hint();
+                assert!(total - skip <= upper.unwrap(),
+                        "{:?} on {:?} after {} matches", re, text, skip);
+            }
+            let mut caps = exec.searcher_str().captures_iter(text);
+            caps.peek_start();
+            let (_, upper) = caps.size_hint();
+            assert!(total <= upper.unwrap(), "{:?} on {:?}", re, text);
+        }
+    }
+
+    #[test]
+    fn captures_iter_reuse_matches_captures_iter() {
+        let exec = ExecBuilder::new(r"([a-z])?([0-9]*)").build().unwrap();
+        let text = "a1 2b33";
+        let mut expected = vec![];
+        for mut locs in exec.searcher_str().captures_iter(text) {
+            expected.push(as_slots(&mut locs).to_vec());
+        }
+        let mut got = vec![];
+        let mut it = exec.searcher_str().captures_iter_reuse(text);
+        while let Some(slots) = it.next_slots() {
+            got.push(slots.to_vec());
+        }
+        assert_eq!(got, expected);
+        assert_eq!(got.len(), 3);
+    }
+
+    #[test]
+    fn captures_collected() {
+        let exec = ExecBuilder::new(r"([a-z])?([0-9]*)").build().unwrap();
+        let text = "a1 2b33";
+        let count = exec.searcher_str().captures_iter(text).count();
+        let caps = exec.searcher_str().captures_collected(text);
+        assert_eq!(caps.len(), count);
+        assert_eq!(caps.slots_len(), 6);
+
+        let mut expected = vec![];
+        for mut locs in exec.searcher_str().captures_iter(text) {
+            expected.push(as_slots(&mut locs).to_vec());
+        }
+        let mut reverse: Vec<_> = caps.rev().collect();
+        reverse.reverse();
+        assert_eq!(reverse, expected);
+        assert!(expected.iter().all(|slots| slots.len() == 6));
+
+        let none = exec.searcher_str().captures_collected("!");
+        assert_eq!(none.slots_len(), 6);
+    }
+
+    #[test]
+    fn slots_to_groups_skips_unmatched() {
+        let exec = ExecBuilder::new(r"(a)(x)?(c)").build().unwrap();
+        let re = exec.searcher_str();
+        let mut locs = re.locations();
+        assert_eq!(re.read_captures_at(&mut locs, "zac", 0), Some((1, 3)));
+        let group = |index, start, end| {
+            CaptureGroup { index: index, start: start, end: end }
+        };
+        assert_eq!(slots_to_groups(as_slots(&mut locs)), vec![
+            group(0, 1, 3), group(1, 1, 2), group(3, 2, 3),
+        ]);
+        assert_eq!(slots_to_groups(&[None, None]), vec![]);
     }
 }
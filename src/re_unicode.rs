@@ -341,6 +341,27 @@ impl Regex {
         })
     }
 
+    /// Returns an iterator for each successive non-overlapping match in
+    /// `text`, yielding only its start and end offsets.
+    ///
+    /// The offsets are always UTF-8 byte indices into `text`, never
+    /// character indices. They always fall on character boundaries, so they
+    /// can be used to slice `text` directly.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"\w+").unwrap();
+    /// let ranges: Vec<(usize, usize)> = re.byte_ranges("δ x").collect();
+    /// assert_eq!(ranges, vec![(0, 2), (3, 4)]);
+    /// # }
+    /// ```
+    pub fn byte_ranges<'r, 't>(&'r self, text: &'t str) -> ByteRanges<'r, 't> {
+        ByteRanges(self.find_iter(text))
+    }
+
     /// Returns an iterator over all the non-overlapping capture groups matched
     /// in `text`. This is operationally the same as `find_iter`, except it
     /// yields information about capturing group matches.
@@ -435,6 +456,46 @@ impl Regex {
         }
     }
 
+    /// Returns an iterator over the pieces of `text` when it is split by
+    /// matches of the regular expression, including the matches themselves.
+    ///
+    /// Each element is either a `SplitPiece::Field`, which corresponds to an
+    /// element yielded by `split`, or a `SplitPiece::Delim`, which
+    /// corresponds to the match that ended the preceding field. Fields and
+    /// delimiters are yielded in the order in which they appear, so
+    /// concatenating every piece reproduces `text`.
+    ///
+    /// A delimiter at the start of `text` is preceded by an empty field, and
+    /// an empty match yields an empty delimiter. As with `split`, no empty
+    /// field is yielded after a delimiter that ends `text`.
+    ///
+    /// This method will *not* copy the text given.
+    ///
+    /// # Example
+    ///
+    /// Tokenize arithmetic while keeping the operators:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::{Regex, SplitPiece};
+    /// # fn main() {
+    /// let re = Regex::new(r"\s*[-+]\s*").unwrap();
+    /// let pieces: Vec<String> = re.split_keep("1 + 2-3").map(|p| {
+    ///     match p {
+    ///         SplitPiece::Field(m) => format!("F({})", m.as_str()),
+    ///         SplitPiece::Delim(m) => format!("D({})", m.as_str()),
+    ///     }
+    /// }).collect();
+    /// assert_eq!(pieces, vec!["F(1)", "D( + )", "F(2)", "D(-)", "F(3)"]);
+    /// # }
+    /// ```
+    pub fn split_keep<'r, 't>(&'r self, text: &'t str) -> SplitKeep<'r, 't> {
+        SplitKeep {
+            finder: self.find_iter(text),
+            last: 0,
+            delim: None,
+        }
+    }
+
     /// Replaces the leftmost-first match with the replacement provided.
     /// The replacement can be a regular string (where `$N` and `$name` are
     /// expanded to match capture groups) or a function that takes the matches'
@@ -867,6 +928,74 @@ impl<'r, 't> Iterator for SplitN<'r, 't> {
     }
 }
 
+/// A piece of text yielded by `SplitKeep`.
+///
+/// `'t` is the lifetime of the string being split.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SplitPiece<'t> {
+    /// Text that isn't matched by the regular expression.
+    Field(Match<'t>),
+    /// Text matched by the regular expression.
+    Delim(Match<'t>),
+}
+
+impl<'t> SplitPiece<'t> {
+    /// Returns true if and only if this piece was matched by the regular
+    /// expression.
+    pub fn is_delim(&self) -> bool {
+        match *self {
+            SplitPiece::Field(_) => false,
+            SplitPiece::Delim(_) => true,
+        }
+    }
+
+    /// Returns the location of this piece in the haystack.
+    pub fn as_match(&self) -> Match<'t> {
+        match *self {
+            SplitPiece::Field(m) | SplitPiece::Delim(m) => m,
+        }
+    }
+}
+
+/// Yields all substrings delimited by a regular expression match, along with
+/// the matches themselves.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being split.
+pub struct SplitKeep<'r, 't> {
+    finder: Matches<'r, 't>,
+    last: usize,
+    delim: Option<Match<'t>>,
+}
+
+impl<'r, 't> Iterator for SplitKeep<'r, 't> {
+    type Item = SplitPiece<'t>;
+
+    fn next(&mut self) -> Option<SplitPiece<'t>> {
+        if let Some(m) = self.delim.take() {
+            return Some(SplitPiece::Delim(m));
+        }
+        let text = self.finder.text();
+        match self.finder.next() {
+            None => {
+                if self.last >= text.len() {
+                    None
+                } else {
+                    let s = Match::new(text, self.last, text.len());
+                    self.last = text.len();
+                    Some(SplitPiece::Field(s))
+                }
+            }
+            Some(m) => {
+                let field = Match::new(text, self.last, m.start());
+                self.last = m.end();
+                self.delim = Some(m);
+                Some(SplitPiece::Field(field))
+            }
+        }
+    }
+}
+
 enum NamedGroups {
     Plugin(&'static [(&'static str, usize)]),
     Dynamic(Arc<HashMap<String, usize>>),
@@ -1114,6 +1243,37 @@ enum CaptureMatchesInner<'r, 't> {
     Plugin(re_trait::CaptureMatches<'t, Plugin>),
 }
 
+impl<'r, 't> CaptureMatches<'r, 't> {
+    /// Returns the start position of the next match without computing its
+    /// capture groups.
+    ///
+    /// The match found is remembered, so a subsequent call to `next` only
+    /// needs to resolve the capture groups of that match. This is useful
+    /// when deciding whether to keep iterating based on where the next match
+    /// begins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(\w)(\d)").unwrap();
+    /// let mut it = re.captures_iter("a1 b2 c3");
+    /// assert_eq!(it.peek_start(), Some(0));
+    /// assert_eq!(&it.next().unwrap()[2], "1");
+    /// assert_eq!(it.peek_start(), Some(3));
+    /// assert_eq!(it.peek_start(), Some(3));
+    /// assert_eq!(&it.next().unwrap()[1], "b");
+    /// # }
+    /// ```
+    pub fn peek_start(&mut self) -> Option<usize> {
+        match self.0 {
+            CaptureMatchesInner::Dynamic(ref mut it) => it.peek_start(),
+            CaptureMatchesInner::Plugin(ref mut it) => it.peek_start(),
+        }
+    }
+}
+
 impl<'r, 't> Iterator for CaptureMatches<'r, 't> {
     type Item = Captures<'t>;
 
@@ -1136,6 +1296,13 @@ impl<'r, 't> Iterator for CaptureMatches<'r, 't> {
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            CaptureMatchesInner::Dynamic(ref it) => it.size_hint(),
+            CaptureMatchesInner::Plugin(ref it) => it.size_hint(),
+        }
+    }
 }
 
 /// An iterator over all non-overlapping matches for a particular string.
@@ -1175,6 +1342,31 @@ impl<'r, 't> Iterator for Matches<'r, 't> {
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            MatchesInner::Dynamic(ref it) => it.size_hint(),
+            MatchesInner::Plugin(ref it) => it.size_hint(),
+        }
+    }
+}
+
+/// An iterator over the UTF-8 byte offsets of all non-overlapping matches for
+/// a particular string.
+///
+/// The iterator yields a `(usize, usize)` value for the start and end of each
+/// match. The iterator stops when no more matches can be found.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the matched string.
+pub struct ByteRanges<'r, 't>(Matches<'r, 't>);
+
+impl<'r, 't> Iterator for ByteRanges<'r, 't> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        self.0.next().map(|m| (m.start(), m.end()))
+    }
 }
 
 /// Replacer describes types that can be used to replace matches in a string.
@@ -38,6 +38,18 @@ pub fn next_utf8(text: &[u8], i: usize) -> usize {
     i + inc
 }
 
+/// Returns the largest possible index of a valid UTF-8 sequence starting
+/// before `i`.
+///
+/// `i` must be greater than `0`.
+pub fn prev_utf8(text: &[u8], i: usize) -> usize {
+    let mut i = i - 1;
+    while i > 0 && text.get(i).map_or(false, |&b| !is_start_byte(b)) {
+        i -= 1;
+    }
+    i
+}
+
 /// Encode the given Unicode character to `dst` as a single UTF-8 sequence.
 ///
 /// If `dst` is not long enough, then `None` is returned. Otherwise, the number
@@ -184,9 +196,21 @@ mod tests {
 
     use super::{
         TAG_CONT, TAG_TWO, TAG_THREE, TAG_FOUR,
-        decode_utf8, decode_last_utf8, encode_utf8,
+        decode_utf8, decode_last_utf8, encode_utf8, next_utf8, prev_utf8,
     };
 
+    #[test]
+    fn prev_utf8_inverts_next_utf8() {
+        let text = "aδ☃💩b".as_bytes();
+        let mut i = 0;
+        while i < text.len() {
+            let next = next_utf8(text, i);
+            assert_eq!(prev_utf8(text, next), i);
+            i = next;
+        }
+        assert_eq!(prev_utf8(text, text.len() + 1), text.len());
+    }
+
     #[test]
     fn prop_roundtrip() {
         fn p(given_cp: char) -> bool {
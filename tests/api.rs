@@ -66,6 +66,37 @@ fn empty_match_captures_iter() {
     assert_eq!(ms, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
 }
 
+#[test]
+fn captures_iter_peek_start() {
+    let re = regex!(r"(.*?)(?:,|$)");
+    let mut it = re.captures_iter(text!("ab,,c"));
+    assert_eq!(it.peek_start(), Some(0));
+    assert_eq!(it.peek_start(), Some(0));
+    assert_eq!(it.next().unwrap().get(1).unwrap().end(), 2);
+    assert_eq!(it.next().unwrap().get(1).unwrap().start(), 3);
+    assert_eq!(it.peek_start(), Some(4));
+    let m = it.next().unwrap().get(1).unwrap();
+    assert_eq!((m.start(), m.end()), (4, 5));
+    assert_eq!(it.peek_start(), None);
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn iter_size_hint() {
+    let re = regex!(r"[0-9]*");
+    let mut it = re.find_iter(text!("a12b"));
+    assert_eq!(it.size_hint(), (0, Some(5)));
+    it.next();
+    assert_eq!(it.size_hint(), (0, Some(4)));
+    assert_eq!(it.count(), 2);
+
+    let mut it = re.captures_iter(text!("a12b"));
+    assert_eq!(it.size_hint(), (0, Some(5)));
+    it.peek_start();
+    assert_eq!(it.size_hint(), (1, Some(5)));
+    assert_eq!(it.count(), 3);
+}
+
 #[test]
 fn capture_names() {
     let re = regex!(r"(.)(?P<a>.)");
@@ -180,3 +211,16 @@ split!(split1, r"\s+", "a b\nc\td\n\t e",
 split!(split2, r"\b", "a b c",
        &[t!(""), t!("a"), t!(" "), t!("b"), t!(" "), t!("c")]);
 split!(split3, r"a$", "a", &[t!("")]);
+
+split_keep!(split_keep1, r"\s+", "a b\nc",
+            &[(false, t!("a")), (true, t!(" ")), (false, t!("b")),
+              (true, t!("\n")), (false, t!("c"))]);
+split_keep!(split_keep2, r",", ",a,,b,",
+            &[(false, t!("")), (true, t!(",")), (false, t!("a")),
+              (true, t!(",")), (false, t!("")), (true, t!(",")),
+              (false, t!("b")), (true, t!(","))]);
+split_keep!(split_keep3, r"\b", "a b",
+            &[(false, t!("")), (true, t!("")), (false, t!("a")),
+              (true, t!("")), (false, t!(" ")), (true, t!("")),
+              (false, t!("b")), (true, t!(""))]);
+split_keep!(split_keep4, r"x", "abc", &[(false, t!("abc"))]);
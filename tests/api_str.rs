@@ -21,6 +21,18 @@ fn empty_match_unicode_captures_iter() {
     assert_eq!(vec![(0, 0), (3, 3), (4, 4), (7, 7), (8, 8)], ms);
 }
 
+#[test]
+fn byte_ranges_multi_byte() {
+    let text = "Ⅰ1 δx Ⅱ";
+    let re = regex!(r"\w+");
+    let ranges: Vec<_> = re.byte_ranges(text).collect();
+    assert_eq!(ranges, vec![(0, 4), (5, 8), (9, 12)]);
+    for &(s, e) in &ranges {
+        assert!(text.is_char_boundary(s) && text.is_char_boundary(e));
+    }
+    assert_eq!(ranges, findall!(re, text));
+}
+
 #[test]
 fn match_as_str() {
     let re = regex!(r"fo+");
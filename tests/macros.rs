@@ -147,3 +147,17 @@ macro_rules! split {
         }
     }
 }
+
+macro_rules! split_keep {
+    ($name:ident, $re:expr, $text:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let re = regex!($re);
+            let pieces: Vec<_> = re.split_keep(t!($text))
+                                   .map(|p| (p.is_delim(),
+                                             match_text!(p.as_match())))
+                                   .collect();
+            assert_eq!($expected, &*pieces);
+        }
+    }
+}